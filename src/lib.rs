@@ -1,15 +1,29 @@
 #![doc = include_str!("../docs/README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 #[doc = include_str!("../docs/sumtype_attr.md")]
 #[cfg(feature = "sumtype")]
 pub use typesum_macros::sumtype;
 
+#[doc = include_str!("../docs/delegate.md")]
+#[cfg(feature = "sumtype")]
+pub use typesum_macros::delegate;
+
 #[doc = include_str!("../docs/kinded.md")]
 #[cfg(feature = "kinded")]
 pub use typesum_macros::kinded;
 
+// `core::error::Error` is the same trait as the one in std, but needs a newer
+// toolchain so it has to be asked for
+#[cfg(feature = "core_error")]
+use core::error::Error;
+#[cfg(all(feature = "std", not(feature = "core_error")))]
+use std::error::Error;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 extern crate self as typesum;
 /// Error type for TryInto impl's on derived sumtypes
 ///
@@ -49,7 +63,7 @@ impl<Source> TryIntoError<Source> {
     /// ```
     pub fn new(source: &'static str, actual: &'static str, expected: &'static str) -> Self {
         Self {
-            discrim: PhantomData::default(),
+            discrim: PhantomData,
             source,
             actual,
             expected,
@@ -94,7 +108,10 @@ impl<Source> TryIntoError<Source> {
     /// Expected variant of the `source` that we failed to convert to
     ///
     /// The expected variant is the one we are expecting the source to
-    /// be
+    /// be. If the conversion accepts several variants (e.g a `TryInto` impl
+    /// for a type shared by more than one variant) this is their names
+    /// separated by `" | "`, which [`expected_kinds`](TryIntoError::expected_kinds)
+    /// splits up again
     pub fn expected(&self) -> &'static str {
         self.expected
     }
@@ -124,15 +141,412 @@ impl<Source> TryIntoError<Source> {
         AnyTryIntoError::new(self.source, self.actual, self.expected)
     }
 }
-impl<S> std::fmt::Display for TryIntoError<S> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<S: VariantKinds> TryIntoError<S> {
+    /// Kind of the [`actual`](TryIntoError::actual) variant
+    ///
+    /// This is only available if the source enum is also `#[kinded]`, and
+    /// is `None` if the error names a variant the source doesn't have
+    ///
+    /// ```
+    /// use typesum::{kinded, sumtype};
+    /// #[sumtype]
+    /// #[kinded]
+    /// enum Sauce {
+    ///     Ketchup(u8),
+    ///     Mayonnaise(u16),
+    ///     Brown(u32),
+    /// }
+    /// let err = Sauce::Ketchup(1).try_as_mayonnaise().unwrap_err();
+    /// assert_eq!(err.actual_kind(), Some(SauceKind::Ketchup));
+    /// assert_eq!(err.expected_kinds().collect::<Vec<_>>(), [SauceKind::Mayonnaise]);
+    /// ```
+    pub fn actual_kind(&self) -> Option<S::Kind> {
+        S::kind_from_name(self.actual)
+    }
+    /// Kinds of the [`expected`](TryIntoError::expected) variants
+    ///
+    /// There can be more than one of these if the conversion accepts
+    /// several variants (e.g a `TryInto` impl for a type shared by more than
+    /// one variant)
+    pub fn expected_kinds(&self) -> impl Iterator<Item = S::Kind> {
+        self.expected
+            .split(expected_variants!())
+            .filter_map(S::kind_from_name)
+    }
+}
+
+/// The [`TryIntoError::expected`] of a conversion which accepts all of the
+/// given variants, or with no variants the separator between them
+#[doc(hidden)]
+#[macro_export]
+macro_rules! expected_variants {
+    () => {
+        " | "
+    };
+    ($first:ident $(, $rest:ident)*) => {
+        concat!(stringify!($first) $(, $crate::expected_variants!(), stringify!($rest))*)
+    };
+}
+impl<S> core::fmt::Display for TryIntoError<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.write_fmt(format_args!(
             "sumtype '{}': variant '{}' expected but was '{}'",
             self.source, self.expected, self.actual
         ))
     }
 }
-impl<S> std::error::Error for TryIntoError<S> {}
+#[cfg(any(feature = "std", feature = "core_error"))]
+impl<S> Error for TryIntoError<S> {}
+
+/// A sum type which can say which variant it is, implemented by every
+/// `#[sumtype]`
+///
+/// This is for generic code like logging which needs to describe any sum
+/// type, and is also where the names in a [`TryIntoError`] come from
+///
+/// ```
+/// use typesum::{sumtype, SumType};
+/// #[sumtype]
+/// enum MySum {
+///     I(i64),
+///     B(bool),
+/// }
+/// fn describe<S: SumType>(s: &S) -> String {
+///     format!("{} ({} of {})", s.variant_name(), s.variant_index() + 1, S::VARIANT_COUNT)
+/// }
+/// assert_eq!(describe(&MySum::B(true)), "B (2 of 2)");
+/// assert_eq!(MySum::VARIANT_NAMES, ["I", "B"]);
+/// ```
+pub trait SumType {
+    /// Names of the variants in declaration order
+    const VARIANT_NAMES: &'static [&'static str];
+    /// How many variants there are
+    const VARIANT_COUNT: usize = Self::VARIANT_NAMES.len();
+    /// Position of this variant in [`VARIANT_NAMES`](SumType::VARIANT_NAMES)
+    fn variant_index(&self) -> usize;
+    /// Name of this variant
+    fn variant_name(&self) -> &'static str {
+        Self::VARIANT_NAMES[self.variant_index()]
+    }
+}
+impl<T: SumType + ?Sized> SumType for &T {
+    const VARIANT_NAMES: &'static [&'static str] = T::VARIANT_NAMES;
+    const VARIANT_COUNT: usize = T::VARIANT_COUNT;
+    fn variant_index(&self) -> usize {
+        T::variant_index(self)
+    }
+    fn variant_name(&self) -> &'static str {
+        T::variant_name(self)
+    }
+}
+impl<T: SumType + ?Sized> SumType for &mut T {
+    const VARIANT_NAMES: &'static [&'static str] = T::VARIANT_NAMES;
+    const VARIANT_COUNT: usize = T::VARIANT_COUNT;
+    fn variant_index(&self) -> usize {
+        T::variant_index(self)
+    }
+    fn variant_name(&self) -> &'static str {
+        T::variant_name(self)
+    }
+}
+
+/// An enum with a kinds enum, implemented by `#[kinded]`
+///
+/// This is what lets a [`TryIntoError`] give back the kinds of the variants
+/// involved rather than just their names
+pub trait VariantKinds {
+    /// The kinds enum
+    type Kind: Copy;
+    /// Kind of the variant called `name`, if there is one
+    fn kind_from_name(name: &str) -> Option<Self::Kind>;
+}
+
+/// Something with a kind, implemented by `#[kinded]` so generic code can get
+/// at the kinds enum
+///
+/// ```
+/// use std::collections::HashMap;
+/// use typesum::{kinded, Kinded};
+/// #[kinded]
+/// enum MyKinded {
+///     I(i64),
+///     B(bool),
+/// }
+/// fn count<T: Kinded>(items: &[T]) -> HashMap<T::Kind, usize> {
+///     let mut counts = HashMap::new();
+///     for item in items {
+///         *counts.entry(item.kind()).or_default() += 1;
+///     }
+///     counts
+/// }
+/// let counts = count(&[MyKinded::I(1), MyKinded::B(true), MyKinded::I(2)]);
+/// assert_eq!(counts[&MyKindedKind::I], 2);
+/// assert_eq!(counts[&MyKindedKind::B], 1);
+/// ```
+pub trait Kinded {
+    /// The kinds enum
+    type Kind: Copy + Eq + core::hash::Hash + Ord + core::fmt::Debug;
+    /// Which kind this is
+    fn kind(&self) -> Self::Kind;
+}
+impl<T: Kinded + ?Sized> Kinded for &T {
+    type Kind = T::Kind;
+    fn kind(&self) -> T::Kind {
+        T::kind(self)
+    }
+}
+impl<T: Kinded + ?Sized> Kinded for &mut T {
+    type Kind = T::Kind;
+    fn kind(&self) -> T::Kind {
+        T::kind(self)
+    }
+}
+#[cfg(feature = "alloc")]
+impl<T: Kinded + ?Sized> Kinded for alloc::boxed::Box<T> {
+    type Kind = T::Kind;
+    fn kind(&self) -> T::Kind {
+        T::kind(self)
+    }
+}
+
+/// A [`TryIntoError`] which also hands back the value that failed to convert
+///
+/// This is what the owned conversions return when the enum is marked with
+/// `#[sumtype(keep_value)]`, so a failed conversion doesn't lose the value
+/// and another one can be tried without cloning first
+///
+/// ```
+/// use typesum::{sumtype, TryIntoError};
+/// #[sumtype(keep_value)]
+/// #[derive(Debug, PartialEq)]
+/// enum MySum {
+///     I(i64),
+///     F(f64),
+///     S(String),
+/// }
+/// let v = MySum::F(1.5);
+/// let n = v.try_into_i().map(|i| i as f64).or_else(|e| e.into_inner().try_into_f());
+/// assert_eq!(n.unwrap(), 1.5);
+///
+/// let e = MySum::S("a".to_owned()).try_into_i().unwrap_err();
+/// assert_eq!(e.error(), TryIntoError::new("MySum", "S", "I"));
+/// assert_eq!(e.into_inner(), MySum::S("a".to_owned()));
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct TryIntoErrorWith<Source> {
+    value: Source,
+    error: TryIntoError<Source>,
+}
+impl<Source> TryIntoErrorWith<Source> {
+    /// Create a new `TryIntoErrorWith` holding on to `value`
+    ///
+    /// ```
+    /// use typesum::TryIntoErrorWith;
+    ///
+    /// let err = TryIntoErrorWith::new(5, "s", "a", "e");
+    /// assert_eq!(err.actual(), "a");
+    /// assert_eq!(err.into_inner(), 5);
+    /// ```
+    pub fn new(
+        value: Source,
+        source: &'static str,
+        actual: &'static str,
+        expected: &'static str,
+    ) -> Self {
+        Self {
+            value,
+            error: TryIntoError::new(source, actual, expected),
+        }
+    }
+    /// Give back the value that failed to convert
+    pub fn into_inner(self) -> Source {
+        self.value
+    }
+    /// The value that failed to convert
+    pub fn value(&self) -> &Source {
+        &self.value
+    }
+    /// The error without the value, see [`TryIntoError`]
+    pub fn error(&self) -> TryIntoError<Source> {
+        self.error
+    }
+    /// Split this into the value and the error
+    pub fn into_parts(self) -> (Source, TryIntoError<Source>) {
+        (self.value, self.error)
+    }
+    /// See [`TryIntoError::source`]
+    pub fn source(&self) -> &'static str {
+        self.error.source()
+    }
+    /// See [`TryIntoError::actual`]
+    pub fn actual(&self) -> &'static str {
+        self.error.actual()
+    }
+    /// See [`TryIntoError::expected`]
+    pub fn expected(&self) -> &'static str {
+        self.error.expected()
+    }
+}
+impl<S> From<TryIntoErrorWith<S>> for TryIntoError<S> {
+    fn from(value: TryIntoErrorWith<S>) -> Self {
+        value.error
+    }
+}
+impl<S> core::fmt::Display for TryIntoErrorWith<S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+#[cfg(any(feature = "std", feature = "core_error"))]
+impl<S: core::fmt::Debug> Error for TryIntoErrorWith<S> {}
+
+/// A sum type with a variant holding a `T`, implemented by
+/// `#[sumtype(has)]`
+///
+/// `I` tells apart the variants when more than one could hold a `T`. Types
+/// which only one variant holds use [`Unique`], so generic code can just ask
+/// for `S: Has<T>`. Types which are held by more than one variant, or which
+/// could be once generic parameters (lifetimes included) are filled in, use
+/// [`At`] with the index of the variant instead, so they have to be picked out
+/// explicitly
+///
+/// ```
+/// use typesum::{sumtype, At, Has};
+/// #[sumtype(has)]
+/// #[derive(Debug, PartialEq)]
+/// enum MySum {
+///     I(i64),
+///     S(String),
+///     #[sumtype(from = false)]
+///     Other(String),
+/// }
+/// fn double<S: Has<i64>>(s: &mut S) {
+///     if let Some(i) = s.get_mut() {
+///         *i *= 2;
+///     }
+/// }
+/// let mut v = MySum::inject(2i64);
+/// double(&mut v);
+/// assert_eq!(v.get::<i64>(), Some(&4));
+/// assert_eq!(v.take::<i64>(), Ok(4));
+///
+/// let v = <MySum as Has<String, At<2>>>::inject("a".to_owned());
+/// assert_eq!(v, MySum::Other("a".to_owned()));
+/// assert_eq!(Has::<String, At<1>>::get(&v), None);
+/// ```
+pub trait Has<T, I = Unique>: Sized {
+    /// The value if it's the variant holding a `T`
+    fn get(&self) -> Option<&T>;
+    /// Mutable version of [`get`](Has::get)
+    fn get_mut(&mut self) -> Option<&mut T>;
+    /// Move the value out if it's the variant holding a `T`, otherwise hand
+    /// back `self`
+    fn take(self) -> Result<T, Self>;
+    /// Wrap `value` in the variant holding a `T`
+    fn inject(value: T) -> Self;
+}
+
+/// Index for [`Has`] when only one variant can hold the type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Unique;
+
+/// Index for [`Has`] picking out the variant at `N` (counting from zero in
+/// declaration order), for types which more than one variant could hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct At<const N: usize>;
+
+/// A variant of the sum type `E`, implemented by the marker types from
+/// `#[sumtype(markers)]` so that generic code can be written over any
+/// variant of an enum
+///
+/// Markers for nested sum types can be chained with [`Then`]
+///
+/// ```
+/// use typesum::{sumtype, Variant};
+/// #[sumtype(markers)]
+/// #[derive(Debug, PartialEq)]
+/// enum MySum {
+///     Int(i64),
+///     Text(String),
+/// }
+/// fn extract<V: Variant<MySum>>(e: &MySum) -> Option<&V::Inner> {
+///     V::get(e)
+/// }
+/// let v = MySum::Int(1);
+/// assert_eq!(extract::<my_sum_variants::Int>(&v), Some(&1));
+/// assert_eq!(extract::<my_sum_variants::Text>(&v), None);
+/// assert_eq!(my_sum_variants::Text::NAME, "Text");
+/// assert_eq!(my_sum_variants::Text::INDEX, 1);
+/// assert_eq!(my_sum_variants::Text::wrap("a".to_owned()), MySum::Text("a".to_owned()));
+/// ```
+pub trait Variant<E> {
+    /// What the variant holds
+    type Inner;
+    /// Name of the variant
+    const NAME: &'static str;
+    /// Position of the variant in the enum, counting from zero
+    const INDEX: usize;
+    /// The contents if `e` is this variant
+    fn get(e: &E) -> Option<&Self::Inner>;
+    /// Mutable version of [`get`](Variant::get)
+    fn get_mut(e: &mut E) -> Option<&mut Self::Inner>;
+    /// Move the contents out if `e` is this variant, otherwise hand `e` back
+    fn into_inner(e: E) -> Result<Self::Inner, E>;
+    /// Put `inner` in this variant
+    fn wrap(inner: Self::Inner) -> E;
+}
+
+/// The variant `B` inside the variant `A`, for following a path through
+/// nested sum types
+///
+/// [`NAME`](Variant::NAME) and [`INDEX`](Variant::INDEX) are those of `B`
+///
+/// ```
+/// use typesum::{sumtype, Then, Variant};
+/// #[sumtype(markers)]
+/// #[derive(Debug, PartialEq)]
+/// enum Inner {
+///     Leaf(i64),
+///     Other(bool),
+/// }
+/// #[sumtype(markers)]
+/// #[derive(Debug, PartialEq)]
+/// enum Outer {
+///     Inner(Inner),
+///     Text(String),
+/// }
+/// type Leaf = Then<outer_variants::Inner, inner_variants::Leaf>;
+/// let v = Outer::Inner(Inner::Leaf(1));
+/// assert_eq!(Leaf::get(&v), Some(&1));
+/// assert_eq!(Leaf::wrap(2), Outer::Inner(Inner::Leaf(2)));
+/// let v = Outer::Inner(Inner::Other(true));
+/// assert_eq!(Leaf::into_inner(v), Err(Outer::Inner(Inner::Other(true))));
+/// ```
+pub struct Then<A, B>(core::marker::PhantomData<(A, B)>);
+
+impl<E, A, B> Variant<E> for Then<A, B>
+where
+    // markers don't hold anything, and this means the contents of `A` live
+    // as long as the enum
+    A: Variant<E> + 'static,
+    B: Variant<A::Inner>,
+{
+    type Inner = B::Inner;
+    const NAME: &'static str = B::NAME;
+    const INDEX: usize = B::INDEX;
+    fn get(e: &E) -> Option<&Self::Inner> {
+        A::get(e).and_then(B::get)
+    }
+    fn get_mut(e: &mut E) -> Option<&mut Self::Inner> {
+        A::get_mut(e).and_then(B::get_mut)
+    }
+    fn into_inner(e: E) -> Result<Self::Inner, E> {
+        B::into_inner(A::into_inner(e)?).map_err(A::wrap)
+    }
+    fn wrap(inner: Self::Inner) -> E {
+        A::wrap(B::wrap(inner))
+    }
+}
 
 /// A [`TryIntoError`] who has forgotten where it comes from
 /// at the type level.
@@ -142,8 +556,8 @@ pub type AnyTryIntoError = TryIntoError<()>;
 
 #[cfg(test)]
 mod tests {
-    use crate::sumtype;
-    use crate::TryIntoError;
+    use crate::{kinded, sumtype};
+    use crate::{At, Has, Kinded, SumType, Then, TryIntoError, Variant};
 
     #[sumtype]
     #[derive(Clone)]
@@ -160,15 +574,423 @@ mod tests {
         Not,
     }
 
+    #[sumtype(impl_try_from, from = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveTryFrom<T> {
+        #[sumtype(impl_try_from = false)]
+        A(T),
+        B(i64),
+        C(i64),
+        Many(Vec<Self>),
+    }
+
+    #[sumtype(impl_try_from_ref, from = false)]
+    #[allow(unused)]
+    enum MySumDeriveTryFromRef<'a, T> {
+        #[sumtype(impl_try_from_ref = false)]
+        A(T),
+        B(&'a str),
+        C(&'a str),
+        D {
+            x: T,
+        },
+        Boxed(Box<Self>),
+    }
+
+    #[sumtype(prefix(as = "get", try_as = "try_get", is = ""), suffix(mut = "ref"))]
+    #[allow(unused)]
+    enum MySumDeriveRenamed {
+        #[sumtype(ignore)]
+        Ignored,
+        #[sumtype(rename = "http_req")]
+        HTTPRequest(i64),
+        V2Payload(bool),
+    }
+
+    #[sumtype(case = "flat", all = false, is = true)]
+    #[allow(unused)]
+    enum MySumDeriveFlat {
+        IntValue(i64),
+    }
+
+    #[sumtype(map, map_into, and_then, inspect)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveCombinators<U> {
+        Int(i64),
+        Pair(i64, U),
+        Named { f: i64 },
+    }
+
+    #[sumtype(unwrap, unwrap_ref, expect, or, or_else, from = false)]
+    #[derive(Debug)]
+    enum MySumDeriveUnwrap<U> {
+        Int(i64),
+        Pair(i64, U),
+        Named { f: i64 },
+    }
+
+    #[sumtype(keep_value, impl_try_from, from = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveKeepValue<T> {
+        #[sumtype(impl_try_from = false)]
+        A(T),
+        B(i64),
+        C(i64, T),
+    }
+
+    #[sumtype(impl_try_into, from = false)]
+    #[kinded]
+    #[allow(unused)]
+    enum MySumDeriveErrorKinds {
+        #[sumtype(impl_try_into = false)]
+        A(bool),
+        B(i64),
+        C(i64),
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum MyError {
+        Conversion(crate::AnyTryIntoError),
+        Other(&'static str),
+    }
+    impl<S> From<TryIntoError<S>> for MyError {
+        fn from(value: TryIntoError<S>) -> Self {
+            Self::Conversion(value.forget_source())
+        }
+    }
+    impl From<TryIntoError<MySumDeriveCustomError>> for &'static str {
+        fn from(value: TryIntoError<MySumDeriveCustomError>) -> Self {
+            value.actual()
+        }
+    }
+
+    #[sumtype(error = MyError, impl_try_from, impl_try_from_ref, from = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveCustomError {
+        A(i64),
+        B(bool),
+        #[sumtype(error = &'static str)]
+        C(String),
+    }
+
+    #[sumtype(crate = "crate", keep_value, impl_try_into, unwrap, ref_enum(kinded))]
+    #[kinded(crate = "crate")]
+    #[allow(unused)]
+    enum MySumDeriveCratePath {
+        A(i64),
+        B(bool),
+    }
+
+    mod delegated {
+        /// # Safety
+        /// always fine
+        #[crate::delegate]
+        pub unsafe trait Counter {
+            const STEP: i64 = 1;
+            fn get(&self) -> i64;
+            fn bump<N: Into<i64>>(&mut self, by: N);
+            fn finish(self) -> i64;
+            /// # Safety
+            /// always fine
+            unsafe fn get_unchecked(&self) -> i64 {
+                self.get()
+            }
+            fn new() -> Self
+            where
+                Self: Sized + Default,
+            {
+                Self::default()
+            }
+        }
+    }
+    use delegated::Counter;
+    unsafe impl Counter for i64 {
+        fn get(&self) -> i64 {
+            *self
+        }
+        fn bump<N: Into<i64>>(&mut self, by: N) {
+            *self += by.into();
+        }
+        fn finish(self) -> i64 {
+            self
+        }
+    }
+    #[derive(Default)]
+    struct Twice(i64);
+    unsafe impl Counter for Twice {
+        fn get(&self) -> i64 {
+            self.0 * 2
+        }
+        fn bump<N: Into<i64>>(&mut self, by: N) {
+            self.0 += by.into();
+        }
+        fn finish(self) -> i64 {
+            -self.0
+        }
+        unsafe fn get_unchecked(&self) -> i64 {
+            0
+        }
+    }
+
+    #[sumtype(delegate(delegated::Counter), all = false)]
+    enum MySumDeriveDelegate<T> {
+        Plain(i64),
+        Twice(Twice),
+        Other(T),
+    }
+
+    mod redelegated {
+        // same name as `delegated::Counter`, which shouldn't clash
+        #[crate::delegate]
+        pub trait Counter {
+            fn get(&self) -> i64;
+        }
+        impl Counter for bool {
+            fn get(&self) -> i64 {
+                *self as i64
+            }
+        }
+    }
+    #[sumtype(delegate(redelegated::Counter), all = false)]
+    enum MySumDeriveDelegateSameName {
+        Flag(bool),
+    }
+
+    #[sumtype(as_dyn(std::fmt::Display + Send), all = false, is)]
+    enum MySumDeriveAsDyn<'a, T> {
+        Int(i64),
+        Str(&'a str),
+        Other(T),
+    }
+
+    #[sumtype(as_dyn(std::fmt::Display), all = false, is)]
+    #[allow(unused)]
+    enum MySumDeriveAsDynUnavailable {
+        Int(i64),
+        Nothing(()),
+    }
+
+    #[sumtype(ref_enum(kinded), from = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveRefEnum<T: Clone> {
+        Int(i64),
+        Pair(i64, T),
+        Named {
+            x: T,
+        },
+        #[sumtype(ignore)]
+        Unit,
+        #[sumtype(rename = "text")]
+        Str(String),
+    }
+
+    #[sumtype(ref_enum, from = false)]
+    #[derive(Debug, Clone, PartialEq)]
+    enum MySumDeriveRefEnumBoxed {
+        Lit(i64),
+        Neg(Box<Self>),
+    }
+
+    #[sumtype(visitor, from = false)]
+    #[allow(unused)]
+    enum MySumDeriveVisitor<T> {
+        Int(i64),
+        Pair(i64, T),
+        Named {
+            visitor: T,
+        },
+        #[sumtype(visitor = false)]
+        Skipped(bool),
+        Neg(Box<Self>),
+    }
+
+    struct CountInts(i64);
+    impl<T> MySumDeriveVisitorVisitor<T> for CountInts {
+        type Output = i64;
+        fn visit_other(&mut self) -> i64 {
+            self.0
+        }
+        fn visit_int(&mut self, v: &i64) -> i64 {
+            self.0 += v;
+            self.0
+        }
+        fn visit_pair(&mut self, (i, _): (&i64, &T)) -> i64 {
+            MySumDeriveVisitorVisitor::<T>::visit_int(self, i)
+        }
+        fn visit_neg(&mut self, v: &Box<MySumDeriveVisitor<T>>) -> i64 {
+            -v.accept(self)
+        }
+    }
+    impl<T> MySumDeriveVisitorVisitorMut<T> for CountInts {
+        type Output = ();
+        fn visit_other(&mut self) {}
+        fn visit_int(&mut self, v: &mut i64) {
+            *v += self.0;
+        }
+    }
+    impl MySumDeriveVisitorVisitorOwned<String> for CountInts {
+        type Output = Option<String>;
+        fn visit_other(&mut self) -> Option<String> {
+            None
+        }
+        fn visit_named(&mut self, v: MySumDeriveVisitorNamed<String>) -> Option<String> {
+            Some(v.visitor)
+        }
+    }
+
+    #[sumtype(match_with, from = false)]
+    #[allow(unused)]
+    enum MySumDeriveMatchWith<T> {
+        Int(i64),
+        Pair(i64, T),
+        Named {
+            on_int: T,
+        },
+        Unit,
+        #[sumtype(ignore)]
+        Ignored(bool),
+    }
+
+    #[sumtype(has, from = false)]
+    #[derive(Debug, PartialEq)]
+    #[allow(unused)]
+    enum MySumDeriveHas<T> {
+        Int(i64),
+        Str(&'static str),
+        Generic(T),
+        Pair(i64, i64),
+        #[sumtype(has = false)]
+        NotHad(bool),
+    }
+
+    #[sumtype(has, from = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveHasLifetimes<'a, 'b> {
+        A(&'a str),
+        B(&'b str),
+        C(i64),
+    }
+
+    mod marked {
+        use crate::sumtype;
+        #[sumtype(markers, all = false)]
+        #[derive(Debug, PartialEq)]
+        #[allow(unused)]
+        pub(super) enum MySumDeriveMarkers<T> {
+            Int(i64),
+            Generic(T),
+            Pair(i64, i64),
+            Unit,
+        }
+        #[sumtype(markers, all = false)]
+        #[derive(Debug, PartialEq)]
+        #[allow(unused)]
+        pub(super) enum Outer {
+            Markers(MySumDeriveMarkers<bool>),
+            Other(()),
+            Nested(Box<Self>),
+        }
+    }
+    use marked::{my_sum_derive_markers_variants as markers, outer_variants, MySumDeriveMarkers};
+
+    #[sumtype(fold, all = false)]
+    #[derive(Debug, PartialEq)]
+    enum MySumDeriveFold<T> {
+        Leaf(T),
+        Neg(Box<Self>),
+        Add(Box<MySumDeriveFold<T>>, Box<MySumDeriveFold<T>>),
+        Call { name: String, args: Vec<Self> },
+        Maybe(Option<Box<Self>>),
+        Pairs(Vec<(String, Self)>),
+        Both([Box<Self>; 2]),
+        Nil,
+    }
+
+    struct DoubleLeaves;
+    impl MySumDeriveFoldFold<i64> for DoubleLeaves {
+        fn fold_leaf(&mut self, v: i64) -> MySumDeriveFold<i64> {
+            MySumDeriveFold::Leaf(v * 2)
+        }
+        fn fold_nil(&mut self) -> MySumDeriveFold<i64> {
+            MySumDeriveFold::Leaf(0)
+        }
+    }
+    struct SumLeaves(i64);
+    impl MySumDeriveFoldVisit<i64> for SumLeaves {
+        fn visit_leaf(&mut self, v: &i64) {
+            self.0 += v;
+        }
+    }
+
     #[sumtype]
     enum MySumDeriveTyped<T> {
         A(T),
     }
 
+    #[kinded(kind_fn = "which")]
+    #[allow(unused)]
+    enum MyKindedRenamed<T> {
+        A(T),
+        B { b: bool },
+        C,
+    }
+
+    #[sumtype]
+    #[kinded]
+    enum MySumDeriveBounded<T: Clone> {
+        A(T),
+    }
+
+    #[sumtype]
+    #[kinded]
+    enum MySumDeriveWhere<T>
+    where
+        T: Clone + Send,
+    {
+        A(T),
+    }
+
+    #[sumtype]
+    #[kinded]
+    enum MySumDeriveConst<const N: usize> {
+        A([u8; N]),
+    }
+
+    #[sumtype]
+    #[kinded]
+    enum MySumDeriveDefaulted<T = i64> {
+        A(T),
+    }
+
+    #[sumtype(impl_try_into, from = false)]
+    enum MySumDeriveTypedTryInto<T> {
+        #[sumtype(impl_try_into = false)]
+        A(T),
+        B(i64),
+        C(Vec<T>),
+    }
+
     #[sumtype]
     enum MySumDeriveLifetimed<'a> {
         A(&'a i32),
     }
+
+    #[sumtype(from = false)]
+    #[allow(unused)]
+    enum MySumNamed<'a, T> {
+        Binary {
+            lhs: Box<MySumNamed<'a, T>>,
+            rhs: Box<MySumNamed<'a, T>>,
+        },
+        Borrowed {
+            name: &'a str,
+        },
+        Call {
+            args: Vec<Self>,
+            callee: Box<Self>,
+        },
+        Leaf(T),
+    }
     #[test]
     fn my_sum_derive_try_into() {
         let v = MySumDerive::Int(64);
@@ -180,6 +1002,428 @@ mod tests {
         assert_eq!(k, Err(TryIntoError::new("MySumDerive", "Int", "Bool")));
     }
 
+    #[test]
+    fn test_derive_try_from() {
+        assert_eq!(i64::try_from(MySumDeriveTryFrom::<()>::C(5)), Ok(5));
+        let r: Result<i64, _> = MySumDeriveTryFrom::<()>::B(1).try_into();
+        assert_eq!(r, Ok(1));
+        assert_eq!(
+            i64::try_from(MySumDeriveTryFrom::A(())),
+            Err(TryIntoError::new("MySumDeriveTryFrom", "A", "B | C"))
+        );
+        let many = MySumDeriveTryFrom::<()>::Many(vec![MySumDeriveTryFrom::B(1)]);
+        assert_eq!(Vec::try_from(many), Ok(vec![MySumDeriveTryFrom::B(1)]));
+    }
+
+    #[test]
+    fn test_derive_try_from_ref() {
+        fn get<'a, T>(v: &'a T) -> Option<&'a &'a str>
+        where
+            &'a &'a str: TryFrom<&'a T>,
+        {
+            v.try_into().ok()
+        }
+        let mut v = MySumDeriveTryFromRef::<()>::C("c");
+        assert_eq!(get(&v), Some(&"c"));
+        let c: &mut &str = (&mut v).try_into().unwrap();
+        *c = "d";
+        assert_eq!(<&&str>::try_from(&v), Ok(&"d"));
+        let d = MySumDeriveTryFromRef::D { x: 1 };
+        let MySumDeriveTryFromRefDRef { x } = (&d).try_into().unwrap();
+        assert_eq!(x, &1);
+        assert_eq!(
+            <&&str>::try_from(&d),
+            Err(TryIntoError::new("MySumDeriveTryFromRef", "D", "B | C"))
+        );
+        let boxed = MySumDeriveTryFromRef::Boxed(Box::new(d));
+        let inner: &Box<_> = (&boxed).try_into().unwrap();
+        assert!(matches!(**inner, MySumDeriveTryFromRef::D { x: 1 }));
+    }
+
+    #[test]
+    fn test_derive_renamed() {
+        let mut v = MySumDeriveRenamed::HTTPRequest(5);
+        assert_eq!(v.get_http_req(), Some(&5));
+        assert_eq!(v.get_http_req_ref(), Some(&mut 5));
+        assert_eq!(v.try_get_http_req_ref(), Ok(&mut 5));
+        assert!(v.http_req());
+        assert!(!v.v_2_payload());
+        assert_eq!(v.into_v_2_payload(), None);
+        assert!(MySumDeriveFlat::IntValue(1).is_intvalue());
+    }
+
+    #[test]
+    fn test_derive_combinators() {
+        type V = MySumDeriveCombinators<bool>;
+        assert_eq!(V::Int(1).map_int(|i| i + 1), V::Int(2));
+        assert_eq!(V::Named { f: 1 }.map_int(|i| i + 1), V::Named { f: 1 });
+        assert_eq!(
+            V::Named { f: 1 }.map_named(|n| MySumDeriveCombinatorsNamed { f: n.f * 3 }),
+            V::Named { f: 3 }
+        );
+        assert_eq!(
+            V::Pair(1, true).map_pair(|(i, b)| (i, !b)),
+            V::Pair(1, false)
+        );
+        assert_eq!(
+            V::Int(1).map_int_into(|i| i.to_string()),
+            Ok("1".to_owned())
+        );
+        assert_eq!(V::Int(1).map_pair_into(|(i, _)| i), Err(V::Int(1)));
+        assert_eq!(
+            V::Int(1).and_then_int(|i| V::Pair(i, true)),
+            V::Pair(1, true)
+        );
+        let mut seen = None;
+        V::Pair(2, false)
+            .inspect_int(|_| unreachable!())
+            .inspect_pair(|(i, _)| seen = Some(*i));
+        assert_eq!(seen, Some(2));
+    }
+
+    #[test]
+    fn sumtype_keep_value() {
+        type V = MySumDeriveKeepValue<String>;
+        let e = V::A("a".to_owned()).try_into_b().unwrap_err();
+        assert_eq!(
+            e.error(),
+            TryIntoError::new("MySumDeriveKeepValue", "A", "B")
+        );
+        assert_eq!(
+            e.to_string(),
+            "sumtype 'MySumDeriveKeepValue': variant 'B' expected but was 'A'"
+        );
+        let (v, e) = e.into_parts();
+        assert_eq!(v.try_into_a(), Ok("a".to_owned()));
+        assert_eq!(
+            TryIntoError::from(crate::TryIntoErrorWith::new(
+                V::B(1),
+                "MySumDeriveKeepValue",
+                "A",
+                "B"
+            )),
+            e
+        );
+        let r: Result<i64, _> = V::C(1, "b".to_owned()).try_into();
+        let v = r.unwrap_err().into_inner();
+        assert_eq!(v.try_into_c(), Ok((1, "b".to_owned())));
+        assert!(V::B(1).try_as_a().is_err());
+    }
+
+    #[test]
+    fn kinded_trait() {
+        fn kinds<K: Kinded>(items: &[K]) -> Vec<K::Kind> {
+            items.iter().map(Kinded::kind).collect()
+        }
+        type V = MyKindedRenamed<i64>;
+        let mut c = V::C;
+        assert_eq!(c.which(), MyKindedRenamedKind::C);
+        assert_eq!(Kinded::kind(&&mut c), MyKindedRenamedKind::C);
+        assert_eq!(
+            kinds(&[V::A(1), V::B { b: true }]),
+            [MyKindedRenamedKind::A, MyKindedRenamedKind::B]
+        );
+        let boxed: Vec<Box<V>> = vec![Box::new(V::C), Box::new(V::A(2))];
+        assert_eq!(
+            kinds(&boxed),
+            [MyKindedRenamedKind::C, MyKindedRenamedKind::A]
+        );
+    }
+
+    #[test]
+    fn sumtype_error_kinds() {
+        let e = MySumDeriveErrorKinds::C(1)
+            .try_into_a()
+            .unwrap_err()
+            .forget_source();
+        assert_eq!(e.actual(), "C");
+        let r: Result<i64, _> = MySumDeriveErrorKinds::A(true).try_into();
+        let e = r.unwrap_err();
+        assert_eq!(e.actual_kind(), Some(MySumDeriveErrorKindsKind::A));
+        assert_eq!(
+            e.expected_kinds().collect::<Vec<_>>(),
+            [MySumDeriveErrorKindsKind::B, MySumDeriveErrorKindsKind::C]
+        );
+        assert_eq!(
+            TryIntoError::<MySumDeriveErrorKinds>::new("", "D", "").actual_kind(),
+            None
+        );
+    }
+
+    #[test]
+    fn sumtype_custom_error() {
+        type V = MySumDeriveCustomError;
+        let conv = |a| MyError::Conversion(TryIntoError::new("MySumDeriveCustomError", a, "B"));
+        assert_eq!(V::A(1).try_into_b(), Err(conv("A")));
+        assert_eq!(V::A(1).try_as_b(), Err(conv("A")));
+        assert_eq!(V::A(1).try_as_b_mut(), Err(conv("A")));
+        assert_eq!(bool::try_from(V::C(String::new())), Err(conv("C")));
+        assert_eq!(<&bool>::try_from(&V::A(1)), Err(conv("A")));
+        assert_eq!(V::B(true).try_into_c(), Err("B"));
+        assert_eq!(String::try_from(V::A(1)), Err("A"));
+        assert_ne!(MyError::Other(""), conv(""));
+    }
+
+    #[test]
+    fn sumtype_crate_path() {
+        let e = MySumDeriveCratePath::A(1).try_into_b().unwrap_err();
+        assert_eq!(e.error().actual_kind(), Some(MySumDeriveCratePathKind::A));
+        assert!(MySumDeriveCratePath::B(true).unwrap_b());
+        assert_eq!(
+            MySumDeriveCratePath::B(true).as_ref_enum().kind(),
+            MySumDeriveCratePathRefKind::B
+        );
+    }
+
+    #[test]
+    fn sumtype_delegate() {
+        type V = MySumDeriveDelegate<i64>;
+        let mut v = V::Twice(Twice(1));
+        v.bump(2u8);
+        assert_eq!(v.get(), 6);
+        assert_eq!(unsafe { v.get_unchecked() }, 0);
+        assert_eq!(v.finish(), -3);
+        let mut v = V::Other(1);
+        v.bump(1);
+        assert_eq!(unsafe { v.get_unchecked() }, 2);
+        assert_eq!(V::Plain(5).finish(), 5);
+        assert_eq!(V::STEP, 1);
+        assert_eq!(Twice::new().get(), 0);
+        assert_eq!(
+            redelegated::Counter::get(&MySumDeriveDelegateSameName::Flag(true)),
+            1
+        );
+    }
+
+    #[test]
+    fn sumtype_as_dyn() {
+        type V<'a> = MySumDeriveAsDyn<'a, bool>;
+        assert_eq!(V::Int(1).as_dyn().to_string(), "1");
+        assert_eq!(V::Str("a").as_dyn().to_string(), "a");
+        let mut v = V::Other(true);
+        assert_eq!(v.as_dyn_mut().to_string(), "true");
+        assert!(v.is_other());
+        // `()` isn't `Display` so there's no `as_dyn`, but the rest still works
+        assert!(MySumDeriveAsDynUnavailable::Int(1).is_int());
+        assert!(MySumDeriveAsDynUnavailable::Nothing(()).is_nothing());
+    }
+
+    #[test]
+    fn sumtype_ref_enum() {
+        type V = MySumDeriveRefEnum<bool>;
+        let mut v = V::Pair(1, true);
+        let r = v.as_ref_enum();
+        assert_eq!(r.as_pair(), Some((&&1, &&true)));
+        assert_eq!(r.kind(), MySumDeriveRefEnumRefKind::Pair);
+        assert_eq!(r.to_owned_enum(), V::Pair(1, true));
+        if let Some((_, b)) = v.as_mut_enum().into_pair() {
+            *b = false;
+        }
+        assert_eq!(v, V::Pair(1, false));
+        assert_eq!(V::Unit.as_ref_enum().to_owned_enum(), V::Unit);
+        assert_eq!(
+            V::Named { x: true }.as_ref_enum().to_owned_enum(),
+            V::Named { x: true }
+        );
+        let mut v = V::Str("a".to_owned());
+        v.as_mut_enum().try_into_text().unwrap().push('b');
+        assert_eq!(v.as_ref_enum().into_text(), Some(&"ab".to_owned()));
+        let v = MySumDeriveRefEnumBoxed::Neg(Box::new(MySumDeriveRefEnumBoxed::Lit(1)));
+        let r = v.as_ref_enum();
+        assert_eq!(r.as_neg().unwrap().as_lit(), Some(&1));
+        assert_eq!(r.to_owned_enum(), v);
+    }
+
+    #[test]
+    fn sumtype_visitor() {
+        type V = MySumDeriveVisitor<String>;
+        let mut count = CountInts(0);
+        assert_eq!(V::Int(2).accept(&mut count), 2);
+        assert_eq!(V::Pair(3, String::new()).accept(&mut count), 5);
+        assert_eq!(V::Skipped(true).accept(&mut count), 5);
+        assert_eq!(V::Neg(Box::new(V::Int(1))).accept(&mut count), -6);
+        count.0 = 5;
+        let mut v = V::Int(1);
+        v.accept_mut(&mut count);
+        assert_eq!(v.as_int(), Some(&6));
+        let named = V::Named {
+            visitor: "a".to_owned(),
+        };
+        assert_eq!(named.accept_owned(&mut count), Some("a".to_owned()));
+        assert_eq!(v.accept_owned(&mut count), None);
+    }
+
+    #[test]
+    fn sumtype_match_with() {
+        type V = MySumDeriveMatchWith<bool>;
+        let describe = |v: V| {
+            v.fold(
+                |i| i.to_string(),
+                |(i, b)| format!("{i} {b}"),
+                |n| format!("named {}", n.on_int),
+                || "unit".to_owned(),
+                |b| format!("ignored {b}"),
+            )
+        };
+        assert_eq!(describe(V::Int(1)), "1");
+        assert_eq!(describe(V::Pair(2, true)), "2 true");
+        assert_eq!(describe(V::Named { on_int: false }), "named false");
+        assert_eq!(describe(V::Unit), "unit");
+        assert_eq!(describe(V::Ignored(true)), "ignored true");
+        assert!(V::Unit.is_unit());
+        let mut v = V::Pair(1, false);
+        v.fold_mut(|_| (), |(_, b)| *b = true, |_| (), || (), |_| ());
+        assert!(v.fold_ref(|_| false, |(_, b)| *b, |n| *n.on_int, || false, |_| false));
+    }
+
+    #[test]
+    fn sumtype_has() {
+        type V = MySumDeriveHas<bool>;
+        fn bump<S: Has<i64>>(s: &mut S) {
+            if let Some(i) = s.get_mut() {
+                *i += 1;
+            }
+        }
+        let mut v = V::inject(1i64);
+        bump(&mut v);
+        assert_eq!(v.get::<i64>(), Some(&2));
+        assert_eq!(v.get::<&str>(), None);
+        let v = v.take::<&str>().unwrap_err();
+        assert_eq!(v.take::<i64>(), Ok(2));
+        let v = <V as Has<bool, At<2>>>::inject(true);
+        assert_eq!(v, V::Generic(true));
+        assert_eq!(Has::<bool, At<2>>::take(v), Ok(true));
+        let mut v = V::Str("a");
+        bump(&mut v);
+        assert_eq!(v.get(), Some(&"a"));
+        type L<'a, 'b> = MySumDeriveHasLifetimes<'a, 'b>;
+        let v = <L as Has<&str, At<1>>>::inject("b");
+        assert_eq!(v, L::B("b"));
+        assert_eq!(Has::<&str, At<0>>::get(&v), None);
+        assert_eq!(L::inject(1i64).take::<i64>(), Ok(1));
+    }
+
+    #[test]
+    fn sumtype_markers() {
+        type V = MySumDeriveMarkers<bool>;
+        fn extract<M: Variant<V>>(v: &V) -> Option<&M::Inner> {
+            M::get(v)
+        }
+        assert_eq!(extract::<markers::Int>(&V::Int(1)), Some(&1));
+        assert_eq!(extract::<markers::Generic>(&V::Int(1)), None);
+        assert_eq!(<markers::Generic as Variant<V>>::NAME, "Generic");
+        assert_eq!(<markers::Generic as Variant<V>>::INDEX, 1);
+        let mut v = <markers::Generic as Variant<V>>::wrap(false);
+        if let Some(b) = <markers::Generic as Variant<V>>::get_mut(&mut v) {
+            *b = true;
+        }
+        assert_eq!(markers::Generic::into_inner(v), Ok(true));
+        assert_eq!(markers::Int::into_inner(V::Unit), Err(V::Unit));
+        // no marker for `Pair` or `Unit`, since they don't hold a single value
+
+        type Deep = Then<outer_variants::Markers, markers::Generic>;
+        let v = Deep::wrap(true);
+        assert_eq!(v, marked::Outer::Markers(V::Generic(true)));
+        assert_eq!(Deep::get(&v), Some(&true));
+        assert_eq!(Deep::NAME, "Generic");
+        assert_eq!(
+            Deep::into_inner(marked::Outer::Other(())),
+            Err(marked::Outer::Other(()))
+        );
+        let v = outer_variants::Nested::wrap(Box::new(marked::Outer::Other(())));
+        assert_eq!(
+            outer_variants::Nested::into_inner(v),
+            Ok(Box::new(marked::Outer::Other(())))
+        );
+    }
+
+    #[test]
+    fn sumtype_sum_type() {
+        fn names<S: SumType>(s: S) -> (&'static str, usize, usize) {
+            (s.variant_name(), s.variant_index(), S::VARIANT_COUNT)
+        }
+        type V = MySumDeriveRefEnum<bool>;
+        assert_eq!(names(V::Unit), ("Unit", 3, 5));
+        let v = V::Pair(1, true);
+        assert_eq!(names(&v), ("Pair", 1, 5));
+        let mut v = V::Named { x: true };
+        assert_eq!(names(&mut v), ("Named", 2, 5));
+        assert_eq!(names(v.as_ref_enum()), ("Named", 2, 5));
+        assert_eq!(V::VARIANT_NAMES, ["Int", "Pair", "Named", "Unit", "Str"]);
+    }
+
+    #[test]
+    fn sumtype_fold() {
+        type V = MySumDeriveFold<i64>;
+        let v = V::Call {
+            name: "f".to_owned(),
+            args: vec![
+                V::Add(Box::new(V::Leaf(1)), Box::new(V::Neg(Box::new(V::Leaf(2))))),
+                V::Maybe(Some(Box::new(V::Leaf(3)))),
+                V::Maybe(None),
+                V::Pairs(vec![("x".to_owned(), V::Leaf(4))]),
+                V::Both([Box::new(V::Leaf(5)), Box::new(V::Nil)]),
+                V::Nil,
+            ],
+        };
+        let mut sum = SumLeaves(0);
+        sum.visit_my_sum_derive_fold(&v);
+        assert_eq!(sum.0, 15);
+        let v = DoubleLeaves.fold_my_sum_derive_fold(v);
+        assert_eq!(
+            v,
+            V::Call {
+                name: "f".to_owned(),
+                args: vec![
+                    V::Add(Box::new(V::Leaf(2)), Box::new(V::Neg(Box::new(V::Leaf(4))))),
+                    V::Maybe(Some(Box::new(V::Leaf(6)))),
+                    V::Maybe(None),
+                    V::Pairs(vec![("x".to_owned(), V::Leaf(8))]),
+                    V::Both([Box::new(V::Leaf(10)), Box::new(V::Leaf(0))]),
+                    V::Leaf(0),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn sumtype_fold_deep() {
+        type V = MySumDeriveFold<i64>;
+        let mut v = V::Leaf(1);
+        for _ in 0..3000 {
+            v = V::Neg(Box::new(v));
+        }
+        let mut sum = SumLeaves(0);
+        sum.visit_my_sum_derive_fold(&v);
+        assert_eq!(sum.0, 1);
+        let mut v = DoubleLeaves.fold_my_sum_derive_fold(v);
+        let mut depth = 0;
+        while let V::Neg(inner) = v {
+            v = *inner;
+            depth += 1;
+        }
+        assert_eq!((depth, v), (3000, V::Leaf(2)));
+    }
+
+    #[test]
+    fn sumtype_unwrap() {
+        type V = MySumDeriveUnwrap<bool>;
+        assert_eq!(V::Int(1).unwrap_int(), 1);
+        assert_eq!(V::Pair(1, true).unwrap_pair_ref(), (&1, &true));
+        assert_eq!(V::Named { f: 2 }.expect_named("named").f, 2);
+        assert_eq!(V::Int(1).int_or(2), 1);
+        assert_eq!(V::Named { f: 1 }.int_or(2), 2);
+        assert_eq!(V::Named { f: 1 }.pair_or_else(|| (3, false)), (3, false));
+        let err = std::panic::catch_unwind(|| V::Int(1).expect_pair("need a pair"))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert_eq!(
+            *err,
+            "need a pair: sumtype 'MySumDeriveUnwrap': variant 'Pair' expected but was 'Int'"
+        );
+    }
+
     #[test]
     fn test_derive_typed() {
         fn assert_typed<T>(val: &MySumDeriveTyped<T>) -> &T {
@@ -187,6 +1431,93 @@ mod tests {
         }
         assert_typed(&MySumDeriveTyped::A(6));
     }
+    #[sumtype(from_tuple)]
+    #[derive(Debug, PartialEq)]
+    enum MySumTuple {
+        Pair(i64, String),
+        Single(i64),
+    }
+
+    #[test]
+    fn test_derive_tuple() {
+        let mut v = MySumTuple::from((1, "a".to_owned()));
+        assert_eq!(v.as_pair(), Some((&1, &"a".to_owned())));
+        v.as_pair_mut().unwrap().1.push('b');
+        assert_eq!(v.try_as_pair(), Ok((&1, &"ab".to_owned())));
+        assert_eq!(
+            v.try_as_single(),
+            Err(TryIntoError::new("MySumTuple", "Pair", "Single"))
+        );
+        assert_eq!(v.into_pair(), Some((1, "ab".to_owned())));
+        assert_eq!(MySumTuple::from(5), MySumTuple::Single(5));
+    }
+
+    #[test]
+    fn test_derive_named() {
+        let mut v = MySumNamed::Binary {
+            lhs: Box::new(MySumNamed::Leaf(1)),
+            rhs: Box::new(MySumNamed::Borrowed { name: "x" }),
+        };
+        let MySumNamedBinaryRef { lhs, rhs } = v.as_binary().unwrap();
+        assert_eq!(lhs.as_leaf(), Some(&1));
+        assert_eq!(rhs.as_borrowed().map(|b| *b.name), Some("x"));
+        **v.as_binary_mut().unwrap().lhs = MySumNamed::Leaf(2);
+        assert_eq!(
+            v.try_as_borrowed().err(),
+            Some(TryIntoError::new("MySumNamed", "Binary", "Borrowed"))
+        );
+        let MySumNamedBinary { lhs, .. } = v.try_into_binary().ok().unwrap();
+        assert_eq!(lhs.into_leaf(), Some(2));
+        let mut v = MySumNamed::Call {
+            args: vec![MySumNamed::Leaf(1)],
+            callee: Box::new(MySumNamed::Borrowed { name: "f" }),
+        };
+        let MySumNamedCallRef { args, callee } = v.as_call().unwrap();
+        assert_eq!(args[0].as_leaf(), Some(&1));
+        assert!(callee.is_borrowed());
+        v.as_call_mut().unwrap().args.push(MySumNamed::Leaf(2));
+        let MySumNamedCall { args, .. } = v.into_call().unwrap();
+        assert_eq!(args.len(), 2);
+    }
+    #[test]
+    fn test_derive_typed_try_into() {
+        let r: Result<i64, _> = MySumDeriveTypedTryInto::<bool>::B(5).try_into();
+        assert_eq!(r, Ok(5));
+        let r: Result<Vec<bool>, _> = MySumDeriveTypedTryInto::A(true).try_into();
+        assert_eq!(
+            r,
+            Err(TryIntoError::new("MySumDeriveTypedTryInto", "A", "C"))
+        );
+        let r: Result<Vec<bool>, _> = MySumDeriveTypedTryInto::C(vec![true]).try_into();
+        assert_eq!(r, Ok(vec![true]));
+    }
+    #[test]
+    fn test_derive_bounded() {
+        let v = MySumDeriveBounded::from(5);
+        assert_eq!(v.as_a(), Some(&5));
+        assert_eq!(v.kind(), MySumDeriveBoundedKind::A);
+    }
+    #[test]
+    fn test_derive_where() {
+        let v = MySumDeriveWhere::from("hello");
+        assert_eq!(v.try_as_a(), Ok(&"hello"));
+        assert_eq!(v.kind(), MySumDeriveWhereKind::A);
+    }
+    #[test]
+    fn test_derive_const() {
+        let v = MySumDeriveConst::from([1, 2, 3]);
+        assert_eq!(v.as_a(), Some(&[1, 2, 3]));
+        assert_eq!(v.kind(), MySumDeriveConstKind::A);
+    }
+    #[test]
+    fn test_derive_defaulted() {
+        let v: MySumDeriveDefaulted = MySumDeriveDefaulted::from(5);
+        assert_eq!(v.into_a(), Some(5i64));
+        assert_eq!(
+            MySumDeriveDefaulted::from(true).kind(),
+            MySumDeriveDefaultedKind::A
+        );
+    }
     #[test]
     fn test_lifetime_deser() {
         fn assert_lifetime(val: MySumDeriveLifetimed<'_>) -> &i32 {
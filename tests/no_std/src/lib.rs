@@ -0,0 +1,38 @@
+#![no_std]
+
+use typesum::{kinded, sumtype, TryIntoError};
+
+#[sumtype(all, error = MyError, keep_value = false)]
+#[kinded]
+#[derive(Debug, PartialEq)]
+pub enum Reading {
+    Temperature(i16),
+    Pressure(u32),
+    Pair(u8, u8),
+    Named { channel: u8 },
+    #[sumtype(ignore)]
+    Nothing,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MyError(pub Option<ReadingKind>);
+
+impl From<TryIntoError<Reading>> for MyError {
+    fn from(value: TryIntoError<Reading>) -> Self {
+        Self(value.actual_kind())
+    }
+}
+
+pub fn temperature(r: Reading) -> Result<i16, MyError> {
+    r.try_into_temperature()
+}
+
+pub fn pressure(r: &Reading) -> Result<&u32, MyError> {
+    r.try_into()
+}
+
+extern crate alloc;
+
+pub fn boxed_kind(r: alloc::boxed::Box<Reading>) -> ReadingKind {
+    typesum::Kinded::kind(&r)
+}
@@ -0,0 +1,157 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use syn::{FnArg, Ident, ItemTrait, TraitItem};
+
+static BRIDGES: AtomicUsize = AtomicUsize::new(0);
+
+fn mentions_self(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Group(g) => mentions_self(g.stream()),
+        TokenTree::Ident(i) => i == "Self",
+        _ => false,
+    })
+}
+
+/// Whether the item was marked `#[delegate(skip)]`, removing the marker
+fn take_skip(attrs: &mut Vec<syn::Attribute>) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("delegate")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("invalid argument, expected `skip`"))
+            }
+        })?;
+    }
+    attrs.retain(|a| !a.path().is_ident("delegate"));
+    Ok(skip)
+}
+
+/// Forwarding impl of a single method, as it appears in the bridge
+fn delegate_fn(f: &syn::TraitItemFn) -> syn::Result<TokenStream> {
+    let mut sig = f.sig.clone();
+    let name = &sig.ident;
+    let Some(receiver) = sig.receiver() else {
+        return Err(syn::Error::new_spanned(
+            &f.sig,
+            format!("`{name}` has no `self` so it can't be delegated, give it a default or mark it #[delegate(skip)]"),
+        ));
+    };
+    if receiver.colon_token.is_some() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "only `self`, `&self` and `&mut self` can be delegated",
+        ));
+    }
+    if mentions_self(sig.output.to_token_stream())
+        || sig
+            .inputs
+            .iter()
+            .any(|a| matches!(a, FnArg::Typed(t) if mentions_self(t.ty.to_token_stream())))
+    {
+        return Err(syn::Error::new_spanned(
+            &f.sig,
+            format!("`{name}` uses `Self` in its arguments or return type so it can't be delegated, mark it #[delegate(skip)] if it has a default"),
+        ));
+    }
+    let mut args = Vec::new();
+    for (i, arg) in sig.inputs.iter_mut().enumerate() {
+        if let FnArg::Typed(t) = arg {
+            let ident = Ident::new(&format!("arg{i}"), Span::mixed_site());
+            t.pat = Box::new(syn::parse_quote! { #ident });
+            args.push(ident);
+        }
+    }
+    let name = &sig.ident;
+    let inner = Ident::new("inner", Span::mixed_site());
+    let mut call = quote! { <$vty as $trait>::#name(#inner, #(#args),*) };
+    if sig.asyncness.is_some() {
+        call = quote! { #call.await };
+    }
+    if sig.unsafety.is_some() {
+        call = quote! { unsafe { #call } };
+    }
+    Ok(quote! {
+        #sig {
+            match self {
+                $(Self::$v(#inner) => #call,)*
+            }
+        }
+    })
+}
+
+pub fn delegate_macro(mut input: ItemTrait) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "generic traits can't be delegated",
+        ));
+    }
+    let mut methods = Vec::new();
+    for item in &mut input.items {
+        match item {
+            TraitItem::Fn(f) => {
+                if take_skip(&mut f.attrs)? {
+                    if f.default.is_none() {
+                        return Err(syn::Error::new_spanned(
+                            &f.sig,
+                            "only functions with a default can be skipped",
+                        ));
+                    }
+                } else if f.sig.receiver().is_some() || f.default.is_none() {
+                    methods.push(delegate_fn(f)?);
+                }
+            }
+            // the default is the best we can do, since each variant could
+            // have a different value
+            TraitItem::Const(c) if c.default.is_some() => {
+                take_skip(&mut c.attrs)?;
+            }
+            TraitItem::Const(c) => {
+                return Err(syn::Error::new_spanned(
+                    c,
+                    "associated consts need a default to be delegated",
+                ));
+            }
+            TraitItem::Type(t) => {
+                return Err(syn::Error::new_spanned(
+                    t,
+                    "associated types can't be delegated",
+                ));
+            }
+            other => {
+                return Err(syn::Error::new_spanned(other, "can't delegate this item"));
+            }
+        }
+    }
+    let ident = &input.ident;
+    // exported macros all live at the crate root, so this needs to be unique
+    // within the crate. It gets re-exported under the name of the trait, so
+    // that `#[sumtype(delegate(path::Trait))]` can find it through the same
+    // path, even from another crate
+    let bridge = format_ident!(
+        "__typesum_delegate_{}_{}",
+        ident,
+        BRIDGES.fetch_add(1, Ordering::Relaxed)
+    );
+    let unsafety = &input.unsafety;
+    Ok(quote! {
+        #input
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! #bridge {
+            (@delegate impl [$($impl:tt)*] [$trait:path] for [$($ty:tt)*] where [$($where:tt)*] variants [$(($v:ident, $vty:ty)),*]) => {
+                #[automatically_derived]
+                #unsafety impl $($impl)* $trait for $($ty)* where $($where)* {
+                    #(#methods)*
+                }
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub use #bridge as #ident;
+    })
+}
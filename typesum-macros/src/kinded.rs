@@ -7,6 +7,8 @@ pub struct Attrs {
     pub name: Option<String>,
     pub kind_fn: Option<String>,
     pub no_kind_fn: bool,
+    /// Path to the typesum crate, set with `crate = ".."`
+    pub krate: Option<syn::Path>,
 }
 
 pub fn kinded_macro(attrs: Attrs, input: DeriveInput) -> syn::Result<TokenStream> {
@@ -21,7 +23,9 @@ pub fn kinded_macro(attrs: Attrs, input: DeriveInput) -> syn::Result<TokenStream
         .kind_fn
         .map(|s| Ident::new(&s, Span::mixed_site()))
         .unwrap_or_else(|| Ident::new("kind", Span::mixed_site()));
+    let krate = attrs.krate.unwrap_or_else(|| syn::parse_quote!(::typesum));
     let orig_input = &input.ident;
+    let (impl_tys, ty_tys, where_clause) = input.generics.split_for_impl();
     let vis = &input.vis;
     let orig_variants = data.variants.iter().map(|v| {
         let fields = match v.fields {
@@ -36,16 +40,28 @@ pub fn kinded_macro(attrs: Attrs, input: DeriveInput) -> syn::Result<TokenStream
         }
     });
     let kinds = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
-    let kinds_fn = if attrs.no_kind_fn {
-        None
+    let (kinds_fn, kinded_impl) = if attrs.no_kind_fn {
+        (None, None)
     } else {
-        Some(quote! {
+        let kinds_fn = quote! {
             #vis fn #kind_fn (&self) -> #name {
                 match self {
                     #(Self::#orig_variants => #name :: #kinds),*
                 }
             }
-        })
+        };
+        // inherent functions win over trait ones, so this is the one above
+        // even if it's also called `kind`
+        let kinded_impl = quote! {
+            #[automatically_derived]
+            impl #impl_tys #krate::Kinded for #orig_input #ty_tys #where_clause {
+                type Kind = #name;
+                fn kind(&self) -> #name {
+                    Self::#kind_fn(self)
+                }
+            }
+        };
+        (Some(kinds_fn), Some(kinded_impl))
     };
     let o = quote! {
         #input
@@ -55,9 +71,20 @@ pub fn kinded_macro(attrs: Attrs, input: DeriveInput) -> syn::Result<TokenStream
             #(#kinds),*
         }
         #[automatically_derived]
-        impl #orig_input {
+        impl #impl_tys #orig_input #ty_tys #where_clause {
             #kinds_fn
         }
+        #kinded_impl
+        #[automatically_derived]
+        impl #impl_tys #krate::VariantKinds for #orig_input #ty_tys #where_clause {
+            type Kind = #name;
+            fn kind_from_name(name: &str) -> ::core::option::Option<#name> {
+                match name {
+                    #(stringify!(#kinds) => ::core::option::Option::Some(#name :: #kinds),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
     };
     Ok(o)
 }
@@ -1,4 +1,6 @@
 use syn::parse_macro_input;
+#[cfg(feature = "sumtype")]
+mod delegate;
 #[cfg(feature = "kinded")]
 mod kinded;
 #[cfg(feature = "sumtype")]
@@ -27,6 +29,9 @@ pub fn kinded(
         } else if meta.path.is_ident("kind_fn") {
             let value: syn::LitStr = meta.value()?.parse()?;
             kind_attrs.kind_fn.replace(value.value());
+        } else if meta.path.is_ident("crate") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            kind_attrs.krate.replace(value.parse()?);
         } else {
             return Err(meta.error("invalid argument"));
         }
@@ -50,15 +55,45 @@ pub fn sumtype(
         add_mut_as: true,
         add_try_into: true,
         add_try_into_impl: false,
+        add_try_from_impl: false,
+        add_try_from_ref_impl: false,
         add_try_as: true,
         add_try_as_mut: true,
+        add_map: false,
+        add_map_into: false,
+        add_and_then: false,
+        add_inspect: false,
+        add_unwrap: false,
+        add_unwrap_ref: false,
+        add_expect: false,
+        add_or: false,
+        add_or_else: false,
+        add_visitor: false,
+        add_match_with: false,
+        add_has: false,
         add_from_impl: true,
+        add_from_tuple_impl: false,
     };
+    let mut options = sum_type::Options::default();
     let parser = syn::meta::parser(|meta| {
-        attrs.add_syn(&meta)?;
+        if !options.add_syn(&meta)? {
+            attrs.add_syn(&meta)?;
+        }
         Ok(())
     });
     parse_macro_input!(attrs_ts with parser);
     let input = parse_macro_input!(item as syn::DeriveInput);
-    sum_type::sumtype_attr(attrs, input).into()
+    sum_type::sumtype_attr(attrs, options, input).into()
+}
+
+#[cfg(feature = "sumtype")]
+#[proc_macro_attribute]
+pub fn delegate(
+    attrs_ts: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let parser = syn::meta::parser(|meta| Err(meta.error("invalid argument")));
+    parse_macro_input!(attrs_ts with parser);
+    let item = parse_macro_input!(item as syn::ItemTrait);
+    handle_syn_result(delegate::delegate_macro(item))
 }
@@ -1,7 +1,9 @@
+use std::collections::{HashMap, HashSet};
+
 use convert_case::{Case, Casing};
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{format_ident, quote, ToTokens};
-use syn::{token::Not, Attribute, Fields};
+use syn::{token::Not, Attribute, Fields, Token};
 
 #[derive(Clone, Copy)]
 struct FullVariant<'a> {
@@ -20,6 +22,393 @@ impl<'a> FullVariant<'a> {
         }
     }
 }
+/// How the payload of a variant is being accessed
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Owned,
+    Ref,
+    Mut,
+}
+impl Access {
+    /// The borrow for this access with an explicit lifetime
+    fn with_lifetime(self, lifetime: Option<&syn::Lifetime>) -> TokenStream {
+        match self {
+            Access::Owned => TokenStream::new(),
+            Access::Ref => quote! { & #lifetime },
+            Access::Mut => quote! { & #lifetime mut },
+        }
+    }
+}
+impl ToTokens for Access {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Access::Owned => {}
+            Access::Ref => tokens.extend(quote! { & }),
+            Access::Mut => tokens.extend(quote! { &mut }),
+        }
+    }
+}
+
+/// Generic parameters and lifetimes mentioned by some tokens
+///
+/// This is a purely syntactic check, so it can be fooled by a type which has the
+/// same name as a generic parameter, but that would be fairly cursed anyway
+#[derive(Default)]
+struct Mentions {
+    idents: HashSet<String>,
+    lifetimes: HashSet<String>,
+}
+impl Mentions {
+    fn of(tokens: impl ToTokens) -> Self {
+        let mut me = Self::default();
+        me.add(tokens.into_token_stream());
+        me
+    }
+    fn add(&mut self, tokens: TokenStream) {
+        let mut tokens = tokens.into_iter();
+        while let Some(tt) = tokens.next() {
+            match tt {
+                TokenTree::Group(g) => self.add(g.stream()),
+                TokenTree::Ident(i) => {
+                    self.idents.insert(i.to_string());
+                }
+                TokenTree::Punct(p) if p.as_char() == '\'' => {
+                    if let Some(TokenTree::Ident(i)) = tokens.next() {
+                        self.lifetimes.insert(i.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    fn param(&self, param: &syn::GenericParam) -> bool {
+        match param {
+            syn::GenericParam::Lifetime(l) => {
+                self.lifetimes.contains(&l.lifetime.ident.to_string())
+            }
+            syn::GenericParam::Type(t) => self.idents.contains(&t.ident.to_string()),
+            syn::GenericParam::Const(c) => self.idents.contains(&c.ident.to_string()),
+        }
+    }
+}
+
+/// The subset of `generics` used by `tys`, so they can be put on a generated
+/// item without upsetting the compiler about unused parameters
+fn generics_used_by<'a>(
+    generics: &syn::Generics,
+    tys: impl IntoIterator<Item = &'a syn::Type>,
+) -> syn::Generics {
+    let mut used = Mentions::default();
+    for ty in tys {
+        used.add(ty.to_token_stream());
+    }
+    let (kept, dropped): (Vec<_>, Vec<_>) =
+        generics.params.iter().cloned().partition(|p| used.param(p));
+    let only_kept = |tokens: &dyn ToTokens| {
+        let m = Mentions::of(tokens);
+        !dropped.iter().any(|p| m.param(p))
+    };
+    let params = kept
+        .into_iter()
+        .map(|mut p| {
+            match &mut p {
+                syn::GenericParam::Lifetime(l) => {
+                    l.bounds = l.bounds.iter().filter(|b| only_kept(b)).cloned().collect()
+                }
+                syn::GenericParam::Type(t) => {
+                    t.bounds = t.bounds.iter().filter(|b| only_kept(b)).cloned().collect()
+                }
+                syn::GenericParam::Const(_) => {}
+            }
+            p
+        })
+        .collect();
+    let where_clause = generics.where_clause.as_ref().map(|w| syn::WhereClause {
+        where_token: w.where_token,
+        predicates: w
+            .predicates
+            .iter()
+            .filter(|p| only_kept(p))
+            .cloned()
+            .collect(),
+    });
+    syn::Generics {
+        lt_token: Some(Default::default()),
+        gt_token: Some(Default::default()),
+        params,
+        where_clause,
+    }
+}
+
+/// A lifetime name that isn't already taken by `generics`
+fn fresh_lifetime(generics: &syn::Generics) -> syn::Lifetime {
+    let taken = generics
+        .lifetimes()
+        .map(|l| l.lifetime.ident.to_string())
+        .collect::<HashSet<_>>();
+    let name = ["a", "b", "c"]
+        .into_iter()
+        .find(|n| !taken.contains(*n))
+        .unwrap_or("__sumtype");
+    syn::Lifetime::new(&format!("'{name}"), Span::call_site())
+}
+
+/// A type parameter name that isn't already taken by `generics`
+fn fresh_type_param(generics: &syn::Generics, name: &str) -> Ident {
+    let taken = generics
+        .params
+        .iter()
+        .filter_map(|p| match p {
+            syn::GenericParam::Type(t) => Some(t.ident.to_string()),
+            syn::GenericParam::Const(c) => Some(c.ident.to_string()),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect::<HashSet<_>>();
+    (0..)
+        .map(|i| match i {
+            0 => name.to_owned(),
+            i => format!("{name}{i}"),
+        })
+        .find(|n| !taken.contains(n))
+        .map(|n| Ident::new(&n, Span::call_site()))
+        .unwrap()
+}
+
+/// Replace `Self` in a field's type, since it means something else anywhere
+/// but the enum's own impls
+fn replace_self(tokens: TokenStream, with: &TokenStream) -> TokenStream {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut out = TokenStream::new();
+    while let Some(tt) = tokens.next() {
+        match tt {
+            TokenTree::Group(g) => {
+                let mut new =
+                    proc_macro2::Group::new(g.delimiter(), replace_self(g.stream(), with));
+                new.set_span(g.span());
+                out.extend([TokenTree::Group(new)]);
+            }
+            // `Self::Assoc` needs to become `<Enum<T>>::Assoc`
+            TokenTree::Ident(i) if i == "Self" => {
+                let with = respan(with.clone(), i.span());
+                match tokens.peek() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' => {
+                        out.extend(quote! { <#with> })
+                    }
+                    _ => out.extend(with),
+                }
+            }
+            tt => out.extend([tt]),
+        }
+    }
+    out
+}
+
+/// `tokens` as if they were written at `span`, so errors point there
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(g) = &tt {
+                tt = TokenTree::Group(proc_macro2::Group::new(
+                    g.delimiter(),
+                    respan(g.stream(), span),
+                ));
+            }
+            tt.set_span(span);
+            tt
+        })
+        .collect()
+}
+
+/// `ty` with `Self` replaced by the enum itself
+fn without_self(ty: &syn::Type, input: &syn::DeriveInput) -> syn::Type {
+    let ident = &input.ident;
+    let (_, ty_tys, _) = input.generics.split_for_impl();
+    syn::parse2(replace_self(
+        ty.to_token_stream(),
+        &quote! { #ident #ty_tys },
+    ))
+    .expect("replacing `Self` with a path keeps it a type")
+}
+
+/// View structs generated for a variant with named fields
+struct NamedPayload {
+    fields: Vec<(Ident, syn::Type)>,
+    owned: Ident,
+    by_ref: Ident,
+    by_mut: Ident,
+    generics: syn::Generics,
+    lifetime: syn::Lifetime,
+}
+impl NamedPayload {
+    fn new(input: &syn::DeriveInput, variant: &syn::Variant, fields: &syn::FieldsNamed) -> Self {
+        let fields = fields
+            .named
+            .iter()
+            .map(|f| (f.ident.clone().unwrap(), without_self(&f.ty, input)))
+            .collect::<Vec<_>>();
+        let owned = format_ident!("{}{}", input.ident, variant.ident);
+        Self {
+            by_ref: format_ident!("{owned}Ref"),
+            by_mut: format_ident!("{owned}Mut"),
+            owned,
+            generics: generics_used_by(&input.generics, fields.iter().map(|(_, t)| t)),
+            lifetime: fresh_lifetime(&input.generics),
+            fields,
+        }
+    }
+    fn ident(&self, access: Access) -> &Ident {
+        match access {
+            Access::Owned => &self.owned,
+            Access::Ref => &self.by_ref,
+            Access::Mut => &self.by_mut,
+        }
+    }
+    /// Declare the view struct for `access`
+    fn definition(
+        &self,
+        vis: &syn::Visibility,
+        input_ident: &Ident,
+        variant: &Ident,
+        access: Access,
+    ) -> TokenStream {
+        let ident = self.ident(access);
+        let names = self.fields.iter().map(|(n, _)| n);
+        let tys = self.fields.iter().map(|(_, t)| t);
+        let lifetime = &self.lifetime;
+        let mut generics = self.generics.clone();
+        if access != Access::Owned {
+            generics.params.insert(
+                0,
+                syn::GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())),
+            );
+        }
+        let borrow = access.with_lifetime(Some(lifetime));
+        let where_clause = &generics.where_clause;
+        let doc = match access {
+            Access::Owned => format!("Fields of [`{input_ident}::{variant}`]"),
+            Access::Ref => format!("Borrowed fields of [`{input_ident}::{variant}`]"),
+            Access::Mut => format!("Mutably borrowed fields of [`{input_ident}::{variant}`]"),
+        };
+        quote! {
+            #[doc = #doc]
+            #[allow(dead_code)]
+            #vis struct #ident #generics #where_clause {
+                #(#vis #names: #borrow #tys),*
+            }
+        }
+    }
+    fn ty(&self, access: Access, lifetime: Option<&syn::Lifetime>) -> TokenStream {
+        let ident = self.ident(access);
+        let lifetime = (access != Access::Owned).then(|| match lifetime {
+            Some(l) => quote! { #l, },
+            None => quote! { '_, },
+        });
+        let params = self.generics.params.iter().map(|p| match p {
+            syn::GenericParam::Lifetime(l) => l.lifetime.to_token_stream(),
+            syn::GenericParam::Type(t) => t.ident.to_token_stream(),
+            syn::GenericParam::Const(c) => c.ident.to_token_stream(),
+        });
+        quote! { #ident < #lifetime #(#params),* > }
+    }
+}
+
+/// The data carried by a variant
+enum Payload {
+    /// `Variant(T)`
+    Single(syn::Type),
+    /// `Variant(A, B, ..)`, accessed as a tuple
+    Tuple(Vec<syn::Type>),
+    /// `Variant { a: A, b: B }`, accessed through generated view structs
+    Named(NamedPayload),
+    /// `Variant`, accessed as `()`
+    Unit,
+}
+impl Payload {
+    /// Pattern binding the contents of the variant
+    fn pattern(&self) -> TokenStream {
+        match self {
+            Payload::Single(_) => quote! { (v) },
+            Payload::Tuple(_) => self.value(Access::Owned),
+            Payload::Named(n) => {
+                let names = n.fields.iter().map(|(n, _)| n);
+                quote! { { #(#names),* } }
+            }
+            Payload::Unit => TokenStream::new(),
+        }
+    }
+    /// Expression using the bindings from [`pattern`](Payload::pattern) to
+    /// build the value handed out for `access`
+    fn value(&self, access: Access) -> TokenStream {
+        match self {
+            Payload::Single(_) => quote! { v },
+            Payload::Tuple(tys) => {
+                let names = (0..tys.len()).map(|i| format_ident!("v{i}"));
+                quote! { ( #(#names),* ) }
+            }
+            Payload::Named(n) => {
+                let ident = n.ident(access);
+                let names = n.fields.iter().map(|(n, _)| n);
+                quote! { #ident { #(#names),* } }
+            }
+            Payload::Unit => quote! { () },
+        }
+    }
+    fn ty(&self, access: Access) -> TokenStream {
+        self.ty_with_lifetime(access, None)
+    }
+    /// Type of the payload for `access`, borrowing for `lifetime` rather than
+    /// an elided one
+    fn ty_with_lifetime(&self, access: Access, lifetime: Option<&syn::Lifetime>) -> TokenStream {
+        let borrow = access.with_lifetime(lifetime);
+        match self {
+            Payload::Single(t) => quote! { #borrow #t },
+            Payload::Tuple(tys) => quote! { ( #(#borrow #tys),* ) },
+            Payload::Named(n) => n.ty(access, lifetime),
+            Payload::Unit => quote! { () },
+        }
+    }
+    /// Type of the payload when moved out of the enum
+    fn owned_ty(&self) -> syn::Type {
+        match self {
+            Payload::Single(t) => t.clone(),
+            Payload::Tuple(_) | Payload::Named(_) | Payload::Unit => {
+                syn::parse2(self.ty(Access::Owned)).unwrap()
+            }
+        }
+    }
+}
+
+/// Whether `ty` is a generic parameter of `generics`, possibly behind a
+/// fundamental type like `&` or `Box`
+///
+/// Conversion traits for these types can't be implemented because they would
+/// overlap with the blanket implementations in `core`
+fn is_uncovered_param(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    match ty {
+        syn::Type::Reference(r) => is_uncovered_param(&r.elem, generics),
+        syn::Type::Paren(p) => is_uncovered_param(&p.elem, generics),
+        syn::Type::Group(g) => is_uncovered_param(&g.elem, generics),
+        syn::Type::Path(p) if p.qself.is_none() => {
+            if generics.type_params().any(|t| p.path.is_ident(&t.ident)) {
+                return true;
+            }
+            let last = p.path.segments.last().unwrap();
+            if last.ident != "Box" && last.ident != "Pin" {
+                return false;
+            }
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return false;
+            };
+            args.args.iter().any(|a| match a {
+                syn::GenericArgument::Type(t) => is_uncovered_param(t, generics),
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
 fn bucketise_by<A, B>(
     mut f: impl FnMut(&A, &A) -> bool,
     input: impl Iterator<Item = (A, B)>,
@@ -35,6 +424,248 @@ fn bucketise_by<A, B>(
     buckets
 }
 
+/// Parse a string which will become part of a function name
+fn parse_name_part(meta: &syn::meta::ParseNestedMeta, allow_empty: bool) -> syn::Result<String> {
+    let value: syn::LitStr = meta.value()?.parse()?;
+    let s = value.value();
+    let valid = s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !s.starts_with(|c: char| c.is_ascii_digit());
+    if !valid || (!allow_empty && s.is_empty()) {
+        return Err(syn::Error::new_spanned(
+            value,
+            "must only contain letters, numbers and underscores, and not start with a number",
+        ));
+    }
+    Ok(s)
+}
+
+/// Parse a flag, which is either just its name or `name = bool`
+fn parse_flag(meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+    if let Ok(v) = meta.value() {
+        Ok(v.parse::<syn::LitBool>()?.value)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Suffixes which can be renamed with `suffix(..)`
+const SUFFIXES: &[&str] = &["mut", "into", "ref", "or", "or_else"];
+
+/// How the names of generated functions are put together
+#[derive(Clone)]
+pub struct Naming {
+    case: Case,
+    prefixes: Vec<(&'static str, String)>,
+    suffixes: Vec<(&'static str, String)>,
+}
+impl Default for Naming {
+    fn default() -> Self {
+        Self {
+            case: Case::Snake,
+            prefixes: Vec::new(),
+            suffixes: Vec::new(),
+        }
+    }
+}
+impl Naming {
+    fn add_syn(&mut self, meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+        if meta.path.is_ident("case") {
+            let value: syn::LitStr = meta.value()?.parse()?;
+            self.case = match value.value().as_str() {
+                "snake" => Case::Snake,
+                "flat" => Case::Flat,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "case must be one of: snake flat",
+                    ))
+                }
+            };
+        } else if meta.path.is_ident("prefix") {
+            let mut prefixes = FAMILIES
+                .iter()
+                .map(|f| f.prefix)
+                .filter(|p| !p.is_empty())
+                .collect::<Vec<_>>();
+            prefixes.dedup();
+            Self::add_parts(meta, &prefixes, &mut self.prefixes)?;
+        } else if meta.path.is_ident("suffix") {
+            Self::add_parts(meta, SUFFIXES, &mut self.suffixes)?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+    fn add_parts(
+        meta: &syn::meta::ParseNestedMeta,
+        valid: &[&'static str],
+        into: &mut Vec<(&'static str, String)>,
+    ) -> syn::Result<()> {
+        meta.parse_nested_meta(|meta| {
+            let Some(key) = valid.iter().find(|k| meta.path.is_ident(k)) else {
+                return Err(meta.error(format!("must be one of: {}", valid.join(" "))));
+            };
+            into.push((key, parse_name_part(&meta, true)?));
+            Ok(())
+        })
+    }
+    /// The part of function names which comes from `variant`
+    fn variant(&self, variant: &Ident) -> String {
+        variant.to_string().to_case(self.case)
+    }
+    /// Name of the function from `family` for a variant whose part of the
+    /// name is `variant`
+    fn method(&self, family: &Family, variant: &str) -> Ident {
+        let lookup = |parts: &[(&str, String)], default: &str| {
+            parts
+                .iter()
+                .rev()
+                .find(|(k, _)| *k == default)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_else(|| default.to_owned())
+        };
+        let name = [
+            Some(lookup(&self.prefixes, family.prefix)),
+            Some(variant.to_owned()),
+            family.suffix.map(|s| lookup(&self.suffixes, s)),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+        Ident::new(&name, Span::call_site())
+    }
+}
+
+/// Options for `#[sumtype]` which aren't just turning functions on and off
+pub struct Options {
+    pub naming: Naming,
+    /// Whether owned conversions hand the value back in a
+    /// `TryIntoErrorWith` when they fail
+    pub keep_value: bool,
+    /// Error type for the `try` functions and conversion impls, set with
+    /// `error = ..`
+    pub error: Option<syn::Type>,
+    /// Path to the typesum crate, set with `crate = ".."`
+    pub krate: syn::Path,
+    /// Traits to implement by forwarding to the variants, which have to be
+    /// marked with `#[delegate]`
+    pub delegates: Vec<syn::Path>,
+    /// Bounds of the trait object for `as_dyn`, if it's wanted
+    pub as_dyn: Option<DynBounds>,
+    /// Whether to generate `{Enum}Ref` and `{Enum}Mut`, set with `ref_enum`
+    pub ref_enum: Option<RefEnum>,
+    /// Whether to generate the `{Enum}Fold` and `{Enum}Visit` traits
+    pub fold: bool,
+    /// Whether to generate the `{enum}_variants` module of marker types
+    pub markers: bool,
+}
+
+/// Options in `ref_enum(..)`
+#[derive(Default)]
+pub struct RefEnum {
+    /// Put `#[kinded]` on the generated enums too
+    kinded: bool,
+}
+
+/// The traits in `as_dyn(..)`
+pub type DynBounds = syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>;
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            naming: Naming::default(),
+            keep_value: false,
+            error: None,
+            krate: syn::parse_quote!(::typesum),
+            delegates: Vec::new(),
+            as_dyn: None,
+            ref_enum: None,
+            fold: false,
+            markers: false,
+        }
+    }
+}
+impl Options {
+    /// Functions generated once for the whole enum rather than per variant
+    fn fixed_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.as_dyn.is_some() {
+            names.extend(["as_dyn", "as_dyn_mut"]);
+        }
+        if self.ref_enum.is_some() {
+            names.extend(["as_ref_enum", "as_mut_enum"]);
+        }
+        names
+    }
+    /// Types generated for the whole enum
+    fn fixed_types(&self, input_ident: &Ident) -> Vec<Ident> {
+        let mut types = Vec::new();
+        if let Some(ref_enum) = &self.ref_enum {
+            types.extend(["Ref", "Mut"].map(|s| format_ident!("{input_ident}{s}")));
+            if ref_enum.kinded {
+                types.extend(["RefKind", "MutKind"].map(|s| format_ident!("{input_ident}{s}")));
+            }
+        }
+        if self.fold {
+            types.extend(["Fold", "Visit"].map(|s| format_ident!("{input_ident}{s}")));
+        }
+        types
+    }
+    /// Returns whether `meta` was one of ours
+    pub fn add_syn(&mut self, meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+        if meta.path.is_ident("keep_value") {
+            self.keep_value = parse_flag(meta)?;
+            return Ok(true);
+        }
+        if meta.path.is_ident("fold") {
+            self.fold = parse_flag(meta)?;
+            return Ok(true);
+        }
+        if meta.path.is_ident("markers") {
+            self.markers = parse_flag(meta)?;
+            return Ok(true);
+        }
+        if meta.path.is_ident("error") {
+            self.error = Some(meta.value()?.parse()?);
+            return Ok(true);
+        }
+        if meta.path.is_ident("delegate") {
+            meta.parse_nested_meta(|meta| {
+                self.delegates.push(meta.path);
+                Ok(())
+            })?;
+            return Ok(true);
+        }
+        if meta.path.is_ident("as_dyn") {
+            let content;
+            syn::parenthesized!(content in meta.input);
+            self.as_dyn = Some(DynBounds::parse_separated_nonempty(&content)?);
+            return Ok(true);
+        }
+        if meta.path.is_ident("ref_enum") {
+            let mut ref_enum = RefEnum::default();
+            if !meta.input.is_empty() && !meta.input.peek(syn::Token![,]) {
+                meta.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("kinded") {
+                        ref_enum.kinded = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("invalid argument, expected `kinded`"))
+                    }
+                })?;
+            }
+            self.ref_enum = Some(ref_enum);
+            return Ok(true);
+        }
+        if meta.path.is_ident("crate") {
+            self.krate = meta.value()?.parse::<syn::LitStr>()?.parse()?;
+            return Ok(true);
+        }
+        self.naming.add_syn(meta)
+    }
+}
+
 macro_rules! define_attrs {
     ($name:ident { $(($ops:ident, $opname:ident)),* }) => {
         #[derive(Default, Debug, Clone, Copy)]
@@ -67,145 +698,1468 @@ macro_rules! define_attrs {
                 else {
                     return Err(meta.error(ERR));
                 }
-                Ok(())
+                Ok(())
+            }
+            pub fn all_false(&self) -> bool {
+                !(false $(|| self.$ops)*)
+            }
+            /// These settings as arguments to `#[sumtype(..)]` on a variant, which
+            /// leaves out `match_with` since only the enum can set that
+            fn to_args(&self) -> TokenStream {
+                let args = [$((stringify!($opname), self.$ops)),*]
+                    .into_iter()
+                    .filter(|(n, _)| *n != "match_with")
+                    .map(|(n, v)| {
+                        let n = Ident::new(n, Span::call_site());
+                        quote! { #n = #v }
+                    });
+                quote! { #(#args),* }
+            }
+
+            pub fn all_false_but_is(&self) -> bool {
+                if self.all_false() {
+                    false
+                } else {
+                    let mut me = self.clone();
+                    me.add_is = false;
+                    me.all_false()
+                }
+            }
+            /// Apply the `#[sumtype]` attribute in `attrs` on top of these, `other` gets
+            /// first go at each property and returns whether it handled it
+            fn add_scope(
+                mut self,
+                attrs: &[Attribute],
+                mut other: impl FnMut(&syn::meta::ParseNestedMeta) -> syn::Result<bool>,
+            ) -> syn::Result<Self> {
+                for attr in attrs {
+                    if attr.path().is_ident("sumtype") {
+                        attr.parse_nested_meta(|meta| {
+                            if !other(&meta)? {
+                                self.add_syn(&meta)?;
+                            }
+                            Ok(())
+                        })?;
+                        return Ok(self);
+                    }
+                }
+                Ok(self)
+            }
+        }
+    };
+}
+
+define_attrs!(Attrs {
+    (add_as, as),
+    (add_into, into),
+    (add_is, is),
+    (add_mut_as, as_mut),
+    (add_try_into, try_into),
+    (add_try_into_impl, impl_try_into),
+    (add_try_from_impl, impl_try_from),
+    (add_try_from_ref_impl, impl_try_from_ref),
+    (add_try_as, try_as),
+    (add_try_as_mut, try_as_mut),
+    (add_map, map),
+    (add_map_into, map_into),
+    (add_and_then, and_then),
+    (add_inspect, inspect),
+    (add_unwrap, unwrap),
+    (add_unwrap_ref, unwrap_ref),
+    (add_expect, expect),
+    (add_or, or),
+    (add_or_else, or_else),
+    (add_visitor, visitor),
+    (add_match_with, match_with),
+    (add_has, has),
+    (add_from_impl, from),
+    (add_from_tuple_impl, from_tuple)
+});
+/// A family of functions generated for each variant, e.g. `as_{}`
+struct Family {
+    prefix: &'static str,
+    suffix: Option<&'static str>,
+    enabled: fn(&Attrs) -> bool,
+}
+const AS: Family = Family {
+    prefix: "as",
+    suffix: None,
+    enabled: |a| a.add_as,
+};
+const AS_MUT: Family = Family {
+    prefix: "as",
+    suffix: Some("mut"),
+    enabled: |a| a.add_mut_as,
+};
+const INTO: Family = Family {
+    prefix: "into",
+    suffix: None,
+    enabled: |a| a.add_into,
+};
+const IS: Family = Family {
+    prefix: "is",
+    suffix: None,
+    enabled: |a| a.add_is,
+};
+const TRY_INTO: Family = Family {
+    prefix: "try_into",
+    suffix: None,
+    enabled: |a| a.add_try_into,
+};
+const TRY_AS: Family = Family {
+    prefix: "try_as",
+    suffix: None,
+    enabled: |a| a.add_try_as,
+};
+const TRY_AS_MUT: Family = Family {
+    prefix: "try_as",
+    suffix: Some("mut"),
+    enabled: |a| a.add_try_as_mut,
+};
+const MAP: Family = Family {
+    prefix: "map",
+    suffix: None,
+    enabled: |a| a.add_map,
+};
+const MAP_INTO: Family = Family {
+    prefix: "map",
+    suffix: Some("into"),
+    enabled: |a| a.add_map_into,
+};
+const AND_THEN: Family = Family {
+    prefix: "and_then",
+    suffix: None,
+    enabled: |a| a.add_and_then,
+};
+const INSPECT: Family = Family {
+    prefix: "inspect",
+    suffix: None,
+    enabled: |a| a.add_inspect,
+};
+const VISIT: Family = Family {
+    prefix: "visit",
+    suffix: None,
+    enabled: |a| a.add_visitor,
+};
+const UNWRAP: Family = Family {
+    prefix: "unwrap",
+    suffix: None,
+    enabled: |a| a.add_unwrap,
+};
+const UNWRAP_REF: Family = Family {
+    prefix: "unwrap",
+    suffix: Some("ref"),
+    enabled: |a| a.add_unwrap_ref,
+};
+const EXPECT: Family = Family {
+    prefix: "expect",
+    suffix: None,
+    enabled: |a| a.add_expect,
+};
+const OR: Family = Family {
+    prefix: "",
+    suffix: Some("or"),
+    enabled: |a| a.add_or,
+};
+const OR_ELSE: Family = Family {
+    prefix: "",
+    suffix: Some("or_else"),
+    enabled: |a| a.add_or_else,
+};
+/// Every family, grouped by prefix
+const FAMILIES: &[Family] = &[
+    AS, AS_MUT, INTO, IS, TRY_INTO, TRY_AS, TRY_AS_MUT, MAP, MAP_INTO, AND_THEN, INSPECT, UNWRAP,
+    UNWRAP_REF, EXPECT, OR, OR_ELSE, VISIT,
+];
+
+impl Attrs {
+    /// Whether any of the enabled functions or impls hand out the payload
+    /// with `access`
+    fn uses_access(&self, access: Access) -> bool {
+        match access {
+            Access::Owned => {
+                self.add_into
+                    || self.add_try_into
+                    || self.add_try_into_impl
+                    || self.add_try_from_impl
+                    || self.add_map
+                    || self.add_map_into
+                    || self.add_and_then
+                    || self.add_unwrap
+                    || self.add_expect
+                    || self.add_or
+                    || self.add_or_else
+                    || self.add_visitor
+                    || self.add_match_with
+            }
+            Access::Ref => {
+                self.add_as
+                    || self.add_try_as
+                    || self.add_try_from_ref_impl
+                    || self.add_inspect
+                    || self.add_unwrap_ref
+                    || self.add_visitor
+                    || self.add_match_with
+            }
+            Access::Mut => {
+                self.add_mut_as
+                    || self.add_try_as_mut
+                    || self.add_try_from_ref_impl
+                    || self.add_visitor
+                    || self.add_match_with
+            }
+        }
+    }
+    /// The settings which make sense for a unit variant, since everything
+    /// else needs a field to hand out
+    fn for_unit(self) -> Self {
+        Self {
+            add_is: self.add_is,
+            add_match_with: self.add_match_with,
+            ..Self::default()
+        }
+    }
+}
+
+fn generate_conv_option<'a>(
+    vis: &'a syn::Visibility,
+    access: Access,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + 'a {
+    move |variants: &[&Ident], payloads: &[&Payload], names: &[Ident]| {
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(access));
+        let tys = payloads.iter().map(|p| p.ty(access));
+        quote! {
+            #(
+                #vis fn #names (#access self) -> Option<#tys> {
+                    match self {
+                        Self::#variants #patterns => Some(#values),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+    }
+}
+/// The closure argument taken by `map_{}` and friends, hygienic since a named
+/// field called `f` would shadow it otherwise
+fn closure_arg() -> Ident {
+    Ident::new("f", Span::mixed_site())
+}
+/// `map_{}`, which rewraps the result in the same variant
+fn generate_map(
+    vis: &syn::Visibility,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + '_ {
+    move |variants, payloads, names| {
+        let f = closure_arg();
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(Access::Owned));
+        let tys = payloads.iter().map(|p| p.ty(Access::Owned));
+        quote! {
+            #(
+                #vis fn #names (self, #f: impl ::core::ops::FnOnce(#tys) -> #tys) -> Self {
+                    match self {
+                        Self::#variants #patterns => {
+                            let #values = #f(#values);
+                            Self::#variants #patterns
+                        }
+                        other => other,
+                    }
+                }
+            )*
+        }
+    }
+}
+/// `map_{}_into`, which hands back `self` if it is some other variant
+fn generate_map_into<'a>(
+    vis: &'a syn::Visibility,
+    generics: &'a syn::Generics,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + 'a {
+    move |variants, payloads, names| {
+        let out = fresh_type_param(generics, "U");
+        let f = closure_arg();
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(Access::Owned));
+        let tys = payloads.iter().map(|p| p.ty(Access::Owned));
+        quote! {
+            #(
+                #vis fn #names <#out> (self, #f: impl ::core::ops::FnOnce(#tys) -> #out) -> ::core::result::Result<#out, Self> {
+                    match self {
+                        Self::#variants #patterns => Ok(#f(#values)),
+                        other => Err(other),
+                    }
+                }
+            )*
+        }
+    }
+}
+fn generate_and_then(
+    vis: &syn::Visibility,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + '_ {
+    move |variants, payloads, names| {
+        let f = closure_arg();
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(Access::Owned));
+        let tys = payloads.iter().map(|p| p.ty(Access::Owned));
+        quote! {
+            #(
+                #vis fn #names (self, #f: impl ::core::ops::FnOnce(#tys) -> Self) -> Self {
+                    match self {
+                        Self::#variants #patterns => #f(#values),
+                        other => other,
+                    }
+                }
+            )*
+        }
+    }
+}
+fn generate_inspect(
+    vis: &syn::Visibility,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + '_ {
+    move |variants, payloads, names| {
+        let f = closure_arg();
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(Access::Ref));
+        let tys = payloads.iter().map(|p| p.ty(Access::Ref));
+        quote! {
+            #(
+                #vis fn #names (&self, #f: impl ::core::ops::FnOnce(#tys)) -> &Self {
+                    if let Self::#variants #patterns = self {
+                        #f(#values);
+                    }
+                    self
+                }
+            )*
+        }
+    }
+}
+
+/// How failed conversions are reported
+#[derive(Clone, Copy)]
+struct Failure<'a> {
+    /// Path to the typesum crate, for the error types
+    krate: &'a syn::Path,
+    /// Whether the value is handed back in a `TryIntoErrorWith`
+    keep_value: bool,
+}
+impl Failure<'_> {
+    /// Same but with a plain `TryIntoError`, for conversions which don't own
+    /// the value
+    fn without_value(self) -> Self {
+        Self {
+            keep_value: false,
+            ..self
+        }
+    }
+    /// The error type for failed conversions
+    fn error_ty(self, input_ident: &Ident, input_tys: &syn::TypeGenerics) -> TokenStream {
+        let krate = self.krate;
+        if self.keep_value {
+            quote! { #krate::TryIntoErrorWith<#input_ident #input_tys> }
+        } else {
+            quote! { #krate::TryIntoError<#input_ident #input_tys> }
+        }
+    }
+}
+
+fn generate_failed_matches(
+    input_ident: &Ident,
+    wanted: impl ToTokens,
+    failure: Failure,
+) -> TokenStream {
+    let krate = failure.krate;
+    let other = Ident::new("other", Span::mixed_site());
+    // this goes through a reference for borrowed matches too, which is what
+    // the impls of `SumType` for references are for
+    let actual = quote! { #krate::SumType::variant_name(&#other) };
+    if failure.keep_value {
+        let actual_name = Ident::new("actual", Span::mixed_site());
+        quote! {
+            #other => {
+                let #actual_name = #actual;
+                Err(#krate::TryIntoErrorWith::new(#other, stringify!(#input_ident), #actual_name, #wanted))
+            }
+        }
+    } else {
+        quote! {
+            #other => Err(#krate::TryIntoError::new(stringify!(#input_ident), #actual, #wanted))
+        }
+    }
+}
+
+/// Generate all the match blocks (match `on` { ... }) where each
+/// variant is matched and all others are errors
+fn generate_try_match_blocks<'a>(
+    variants: &'a [&'a Ident],
+    payloads: &'a [&'a Payload],
+    access: Access,
+    input_ident: &'a Ident,
+    failure: Failure<'a>,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    variants.iter().zip(payloads).map(move |(v, p)| {
+        let failed = generate_failed_matches(input_ident, quote! { stringify!(#v) }, failure);
+        let pattern = p.pattern();
+        let value = p.value(access);
+        quote! {
+            match self {
+                #input_ident :: #v #pattern => Ok(#value),
+                #failed
+            }
+        }
+    })
+}
+
+/// `unwrap_{}` and friends, which panic with the message from
+/// [`TryIntoError`] if `self` is the wrong variant. If `expect` is set they
+/// also take a message to put in front of it
+fn generate_unwrap<'a>(
+    vis: &'a syn::Visibility,
+    input_ident: &'a Ident,
+    access: Access,
+    expect: bool,
+    failure: Failure<'a>,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + 'a {
+    move |variants, payloads, names| {
+        let failure = failure.without_value();
+        let blocks = generate_try_match_blocks(variants, payloads, access, input_ident, failure);
+        let krate = failure.krate;
+        let tys = payloads.iter().map(|p| p.ty(access));
+        let msg = Ident::new("msg", Span::mixed_site());
+        let (param, panic) = if expect {
+            (
+                Some(quote! { , #msg: &str }),
+                quote! { ::core::panic!("{}: {}", #msg, e) },
+            )
+        } else {
+            (None, quote! { ::core::panic!("{}", e) })
+        };
+        quote! {
+            #(
+                #[track_caller]
+                #vis fn #names (#access self #param) -> #tys {
+                    let r: ::core::result::Result<_, #krate::TryIntoError<Self>> = #blocks;
+                    match r {
+                        Ok(v) => v,
+                        Err(e) => #panic,
+                    }
+                }
+            )*
+        }
+    }
+}
+
+/// `{}_or` and `{}_or_else`, which fall back to a default for other variants
+fn generate_or(
+    vis: &syn::Visibility,
+    lazy: bool,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + '_ {
+    move |variants, payloads, names| {
+        let patterns = payloads.iter().map(|p| p.pattern());
+        let values = payloads.iter().map(|p| p.value(Access::Owned));
+        let tys = payloads
+            .iter()
+            .map(|p| p.ty(Access::Owned))
+            .collect::<Vec<_>>();
+        let default = Ident::new("default", Span::mixed_site());
+        let (param_tys, fallback) = if lazy {
+            let tys = tys
+                .iter()
+                .map(|t| quote! { impl ::core::ops::FnOnce() -> #t });
+            (tys.collect::<Vec<_>>(), quote! { #default() })
+        } else {
+            (
+                tys.iter().map(|t| t.to_token_stream()).collect(),
+                quote! { #default },
+            )
+        };
+        quote! {
+            #(
+                #vis fn #names (self, #default: #param_tys) -> #tys {
+                    match self {
+                        Self::#variants #patterns => #values,
+                        _ => #fallback,
+                    }
+                }
+            )*
+        }
+    }
+}
+
+/// The `{Enum}Visitor` traits (one for each kind of access) along with the
+/// `accept` functions which dispatch to them. Variants without a method of
+/// their own, of which there are `others`, go to `visit_other`
+fn generate_visitors<'a>(
+    vis: &'a syn::Visibility,
+    input: &'a syn::DeriveInput,
+    others: bool,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> (TokenStream, TokenStream) + 'a {
+    move |variants, payloads, names| {
+        if variants.is_empty() {
+            return Default::default();
+        }
+        let input_ident = &input.ident;
+        let generics = &input.generics;
+        let where_clause = &generics.where_clause;
+        let (_, ty_tys, _) = generics.split_for_impl();
+        let visitor_ty = fresh_type_param(generics, "V");
+        let visitor = Ident::new("visitor", Span::mixed_site());
+        let patterns = payloads.iter().map(|p| p.pattern()).collect::<Vec<_>>();
+        let mut defs = TokenStream::new();
+        let mut fns = TokenStream::new();
+        for (access, trait_suffix, accept) in [
+            (Access::Ref, "", "accept"),
+            (Access::Mut, "Mut", "accept_mut"),
+            (Access::Owned, "Owned", "accept_owned"),
+        ] {
+            let trait_ident = format_ident!("{input_ident}Visitor{trait_suffix}");
+            let accept = Ident::new(accept, Span::call_site());
+            let tys = payloads.iter().map(|p| p.ty(access));
+            let values = payloads.iter().map(|p| p.value(access));
+            // fully qualified since one type could implement more than one
+            // of these traits
+            let qualified = quote! { <#visitor_ty as #trait_ident #ty_tys> };
+            let other = others.then(|| quote! { _ => #qualified::visit_other(#visitor), });
+            let doc = format!("Visitor for [`{input_ident}`], see [`{input_ident}::{accept}`]");
+            defs.extend(quote! {
+                #[doc = #doc]
+                #vis trait #trait_ident #generics #where_clause {
+                    type Output;
+                    /// Called for any variant which doesn't have its own method
+                    fn visit_other(&mut self) -> Self::Output;
+                    #(
+                        #[allow(clippy::boxed_local)]
+                        fn #names(&mut self, _value: #tys) -> Self::Output {
+                            <Self as #trait_ident #ty_tys>::visit_other(self)
+                        }
+                    )*
+                }
+            });
+            fns.extend(quote! {
+                #vis fn #accept<#visitor_ty: #trait_ident #ty_tys>(#access self, #visitor: &mut #visitor_ty) -> #visitor_ty::Output {
+                    match self {
+                        #(Self::#variants #patterns => #qualified::#names(#visitor, #values),)*
+                        #other
+                    }
+                }
+            });
+        }
+        (defs, fns)
+    }
+}
+
+/// `fold`, `fold_ref` and `fold_mut`, which take a closure for every variant
+/// (named after `names`) and call the one for the variant `self` is
+fn generate_match_with(
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    variants: &[&Ident],
+    payloads: &[&Payload],
+    names: &[&String],
+) -> TokenStream {
+    let out = fresh_type_param(generics, "R");
+    // named fields could shadow these otherwise
+    let closures = names
+        .iter()
+        .map(|n| Ident::new(&format!("on_{n}"), Span::mixed_site()))
+        .collect::<Vec<_>>();
+    let patterns = payloads.iter().map(|p| p.pattern()).collect::<Vec<_>>();
+    let mut fns = TokenStream::new();
+    for (access, name) in [
+        (Access::Owned, "fold"),
+        (Access::Ref, "fold_ref"),
+        (Access::Mut, "fold_mut"),
+    ] {
+        let name = Ident::new(name, Span::call_site());
+        // unit variants get closures without any arguments
+        let (tys, values): (Vec<_>, Vec<_>) = payloads
+            .iter()
+            .map(|p| match p {
+                Payload::Unit => (TokenStream::new(), TokenStream::new()),
+                p => (p.ty(access), p.value(access)),
+            })
+            .unzip();
+        fns.extend(quote! {
+            #vis fn #name<#out>(#access self, #(#closures: impl ::core::ops::FnOnce(#tys) -> #out),*) -> #out {
+                match self {
+                    #(Self::#variants #patterns => #closures(#values),)*
+                }
+            }
+        });
+    }
+    fns
+}
+
+/// Impls of `Has` for the single field `variants` (along with the index of
+/// each among all the variants), plus `get` and friends which go through it
+fn generate_has(
+    vis: &syn::Visibility,
+    krate: &syn::Path,
+    input: &syn::DeriveInput,
+    variants: &[(&Ident, &syn::Type, usize)],
+) -> (TokenStream, TokenStream) {
+    if variants.is_empty() {
+        return Default::default();
+    }
+    let input_ident = &input.ident;
+    let generics = &input.generics;
+    let (impl_tys, ty_tys, where_clause) = generics.split_for_impl();
+    // types which mention a generic parameter, lifetimes included, could turn
+    // out to be the same as one of the others, which would make the impls
+    // overlap
+    let is_generic = |ty: &syn::Type| {
+        let m = Mentions::of(ty);
+        generics.params.iter().any(|p| m.param(p))
+    };
+    let value = Ident::new("value", Span::mixed_site());
+    let impls = variants.iter().map(|(v, ty, i)| {
+        let unique = !is_generic(ty) && variants.iter().filter(|(_, t, _)| t == ty).count() == 1;
+        let index = if unique {
+            quote! { #krate::Unique }
+        } else {
+            quote! { #krate::At<#i> }
+        };
+        quote! {
+            #[automatically_derived]
+            impl #impl_tys #krate::Has<#ty, #index> for #input_ident #ty_tys #where_clause {
+                fn get(&self) -> ::core::option::Option<&#ty> {
+                    match self {
+                        Self::#v(#value) => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+                fn get_mut(&mut self) -> ::core::option::Option<&mut #ty> {
+                    match self {
+                        Self::#v(#value) => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+                fn take(self) -> ::core::result::Result<#ty, Self> {
+                    match self {
+                        Self::#v(#value) => ::core::result::Result::Ok(#value),
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+                fn inject(#value: #ty) -> Self {
+                    Self::#v(#value)
+                }
+            }
+        }
+    });
+    let t = fresh_type_param(generics, "T");
+    let fns = quote! {
+        /// The value if this is the only variant holding a `T`, see
+        /// [`Has`](#krate::Has)
+        #vis fn get<#t>(&self) -> ::core::option::Option<&#t>
+        where
+            Self: #krate::Has<#t>,
+        {
+            #krate::Has::<#t>::get(self)
+        }
+        /// Mutable version of `get`
+        #vis fn get_mut<#t>(&mut self) -> ::core::option::Option<&mut #t>
+        where
+            Self: #krate::Has<#t>,
+        {
+            #krate::Has::<#t>::get_mut(self)
+        }
+        /// Move the value out if this is the only variant holding a `T`,
+        /// otherwise hand back `self`
+        #vis fn take<#t>(self) -> ::core::result::Result<#t, Self>
+        where
+            Self: #krate::Has<#t>,
+        {
+            #krate::Has::<#t>::take(self)
+        }
+        /// Wrap `value` in the only variant holding a `T`
+        #vis fn inject<#t>(#value: #t) -> Self
+        where
+            Self: #krate::Has<#t>,
+        {
+            #krate::Has::<#t>::inject(#value)
+        }
+    };
+    (quote! { #(#impls)* }, fns)
+}
+
+/// Error types given with `error = ..`, by variant
+type CustomErrors = HashMap<Ident, syn::Type>;
+
+/// Make `body`, which fails with `error`, fail with `custom` instead if there
+/// is one. Returns the error type along with the new body
+fn convert_error(
+    body: TokenStream,
+    error: &TokenStream,
+    custom: Option<&syn::Type>,
+) -> (TokenStream, TokenStream) {
+    match custom {
+        Some(custom) => (
+            custom.to_token_stream(),
+            quote! {
+                let r: ::core::result::Result<_, #error> = #body;
+                r.map_err(::core::convert::From::from)
+            },
+        ),
+        None => (error.clone(), body),
+    }
+}
+
+fn generate_conv_try<'a>(
+    vis: &'a syn::Visibility,
+    input_ident: &'a Ident,
+    access: Access,
+    input_tys: &'a syn::TypeGenerics,
+    failure: Failure<'a>,
+    errors: &'a CustomErrors,
+) -> impl FnOnce(&[&Ident], &[&Payload], &[Ident]) -> TokenStream + 'a {
+    move |variants, payloads, names| {
+        let blocks = generate_try_match_blocks(variants, payloads, access, input_ident, failure);
+        let tys = payloads.iter().map(|p| p.ty(access));
+        let error = failure.error_ty(input_ident, input_tys);
+        let (error_tys, blocks): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .zip(blocks)
+            .map(|(v, block)| convert_error(block, &error, errors.get(*v)))
+            .unzip();
+        quote! {
+            #(
+                #vis fn #names (#access self) -> ::core::result::Result<#tys, #error_tys> {
+                    #blocks
+                }
+            )*
+        }
+    }
+}
+
+/// Variants grouped by their type, see [`conversion_buckets`]
+type Buckets<'a> = Vec<(syn::Type, Vec<(&'a Ident, &'a Payload)>)>;
+
+/// Group the variants enabled by `filter` by their type, so that overlapping
+/// types get a single conversion impl which accepts any of them. `access` is
+/// how the impls take the enum
+#[allow(clippy::too_many_arguments)]
+fn conversion_buckets<'a>(
+    variants: &'a [(Attrs, Ident)],
+    payloads: &'a [Payload],
+    input_ident: &Ident,
+    generics: &syn::Generics,
+    access: Access,
+    errors: &CustomErrors,
+    option: &str,
+    filter: impl Fn(&Attrs) -> bool,
+) -> syn::Result<Buckets<'a>> {
+    let enabled = variants
+        .iter()
+        .zip(payloads)
+        .filter(|((a, _), _)| filter(a))
+        .map(|((_, v), p)| (p.owned_ty(), (v, p)))
+        .collect::<Vec<_>>();
+    if let Some((ty, _)) = enabled
+        .iter()
+        .find(|(t, _)| is_uncovered_param(t, generics))
+    {
+        return Err(syn::Error::new_spanned(
+            ty,
+            format!("{option} can't be used for a generic parameter as it would conflict with the blanket implementations in core. Disable it for this variant with #[sumtype({option} = false)]"),
+        ));
+    }
+    // `impl From<T> for Box<T>` already converts the enum into a box of itself
+    if let Some((ty, _)) = enabled.iter().find(|(t, _)| {
+        access == Access::Owned && wrapped(t, "Box").is_some_and(|t| is_node(t, input_ident))
+    }) {
+        return Err(syn::Error::new_spanned(
+            ty,
+            format!("{option} can't be used for a box of the enum as it would conflict with the blanket implementations in core. Disable it for this variant with #[sumtype({option} = false)]"),
+        ));
+    }
+    let buckets = bucketise_by(|l, r| l == r, enabled.into_iter());
+    for (_, bucket) in &buckets {
+        let error = |v: &Ident| errors.get(v).map(|e| e.to_token_stream().to_string());
+        let (first, _) = bucket[0];
+        if let Some((other, _)) = bucket.iter().find(|(v, _)| error(v) != error(first)) {
+            return Err(syn::Error::new_spanned(
+                other,
+                format!("`{other}` shares its {option} impl with `{first}` so they must have the same error type"),
+            ));
+        }
+    }
+    Ok(buckets)
+}
+
+/// Match arms for a bucket from [`conversion_buckets`], which accept any of
+/// the variants in it and error on everything else
+fn generate_bucket_arms(
+    variants: &[(&Ident, &Payload)],
+    access: Access,
+    input_ident: &Ident,
+    failure: Failure,
+) -> TokenStream {
+    let krate = failure.krate;
+    let names = variants.iter().map(|(i, _)| i);
+    let failed = generate_failed_matches(
+        input_ident,
+        quote! { #krate::expected_variants!(#(#names),*) },
+        failure,
+    );
+    let idents = variants.iter().map(|(i, _)| i);
+    let patterns = variants.iter().map(|(_, p)| p.pattern());
+    let values = variants.iter().map(|(_, p)| p.value(access));
+    quote! {
+        #(#input_ident :: #idents #patterns => Ok(#values),)*
+        #failed
+    }
+}
+
+/// Every variant of the enum along with the type of its field, for things
+/// which need to handle all of them the same way. It's an error for `option`
+/// if any of them don't have exactly one
+fn single_field_variants<'a>(
+    input: &'a syn::DeriveInput,
+    option: &str,
+) -> syn::Result<Vec<(&'a Ident, &'a syn::Type)>> {
+    let syn::Data::Enum(data) = &input.data else {
+        unreachable!()
+    };
+    data.variants
+        .iter()
+        .map(|v| match &v.fields {
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => Ok((&v.ident, &f.unnamed[0].ty)),
+            _ => Err(syn::Error::new_spanned(
+                v,
+                format!("{option} needs every variant to have exactly one unnamed field"),
+            )),
+        })
+        .collect()
+}
+
+/// `as_dyn` and `as_dyn_mut`, which give the field of any variant as a trait
+/// object with `bounds`
+fn generate_as_dyn(
+    vis: &syn::Visibility,
+    bounds: &DynBounds,
+    input: &syn::DeriveInput,
+) -> syn::Result<TokenStream> {
+    let variants = single_field_variants(input, "as_dyn")?;
+    let (vs, tys): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+    let inner = Ident::new("inner", Span::mixed_site());
+    let arms = quote! {
+        match self {
+            #(Self::#vs(#inner) => #inner,)*
+        }
+    };
+    // a bound on a concrete type which doesn't hold is an error rather than
+    // making the function unavailable, unless it's behind a `for<..>`
+    let lifetime = fresh_lifetime(&input.generics);
+    Ok(quote! {
+        #vis fn as_dyn(&self) -> &(dyn #bounds)
+        where
+            #(for<#lifetime> #tys: #bounds),*
+        {
+            #arms
+        }
+        #vis fn as_dyn_mut(&mut self) -> &mut (dyn #bounds)
+        where
+            #(for<#lifetime> #tys: #bounds),*
+        {
+            #arms
+        }
+    })
+}
+
+/// `{Enum}Ref` and `{Enum}Mut`, which hold a borrow of the field(s) of a
+/// variant. These get the same `#[sumtype]` (with each variant's settings
+/// copied across from `variant_args`). Returns the definitions along with
+/// `as_ref_enum` and `as_mut_enum` for the original
+fn generate_ref_enums(
+    attrs: Attrs,
+    options: &Options,
+    ref_enum: &RefEnum,
+    input: &syn::DeriveInput,
+    variant_args: &[TokenStream],
+) -> (TokenStream, TokenStream) {
+    let syn::Data::Enum(data) = &input.data else {
+        unreachable!()
+    };
+    let input_ident = &input.ident;
+    let vis = &input.vis;
+    let (_, ty_tys, _) = input.generics.split_for_impl();
+    let lifetime = fresh_lifetime(&input.generics);
+    let mut generics = input.generics.clone();
+    generics.params.insert(
+        0,
+        syn::GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())),
+    );
+    let (impl_tys, ref_ty_tys, where_clause) = generics.split_for_impl();
+    let krate = &options.krate;
+    let krate_str = krate.to_token_stream().to_string();
+    let kinded = ref_enum
+        .kinded
+        .then(|| quote! { #[#krate::kinded(crate = #krate_str)] });
+    // patterns which bind every field, and the names they're bound to
+    let bindings = data
+        .variants
+        .iter()
+        .map(|v| match &v.fields {
+            Fields::Named(f) => {
+                let names = f.named.iter().map(|f| f.ident.clone().unwrap());
+                let names = names.collect::<Vec<_>>();
+                (quote! { { #(#names),* } }, names)
+            }
+            Fields::Unnamed(f) => {
+                let names = (0..f.unnamed.len())
+                    .map(|i| Ident::new(&format!("f{i}"), Span::mixed_site()))
+                    .collect::<Vec<_>>();
+                (quote! { ( #(#names),* ) }, names)
+            }
+            Fields::Unit => (TokenStream::new(), Vec::new()),
+        })
+        .collect::<Vec<_>>();
+    let variant_idents = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let patterns = bindings.iter().map(|(p, _)| p).collect::<Vec<_>>();
+
+    let mut defs = TokenStream::new();
+    let mut fns = TokenStream::new();
+    for access in [Access::Ref, Access::Mut] {
+        let (ident, fn_name) = match access {
+            Access::Ref => (
+                format_ident!("{input_ident}Ref"),
+                format_ident!("as_ref_enum"),
+            ),
+            _ => (
+                format_ident!("{input_ident}Mut"),
+                format_ident!("as_mut_enum"),
+            ),
+        };
+        let borrow = access.with_lifetime(Some(&lifetime));
+        let mut variants = data.variants.clone();
+        for (v, args) in variants.iter_mut().zip(variant_args) {
+            v.attrs = vec![syn::parse_quote! { #[sumtype(#args)] }];
+            v.discriminant = None;
+            for f in v.fields.iter_mut() {
+                // `Self` would be the borrowed enum in there
+                let ty = without_self(&f.ty, input);
+                f.ty = syn::parse_quote! { #borrow #ty };
+                f.attrs.clear();
+            }
+        }
+        let doc = format!("Borrowed version of [`{input_ident}`]");
+        let def: syn::DeriveInput = syn::parse_quote! {
+            #[doc = #doc]
+            #kinded
+            #[allow(dead_code)]
+            #vis enum #ident #impl_tys #where_clause {
+                #variants
+            }
+        };
+        let def = syn::DeriveInput {
+            generics: generics.clone(),
+            ..def
+        };
+        let ref_options = Options {
+            naming: options.naming.clone(),
+            krate: krate.clone(),
+            ..Options::default()
+        };
+        defs.extend(sumtype_attr(attrs, ref_options, def));
+        fns.extend(quote! {
+            #vis fn #fn_name<#lifetime>(#borrow self) -> #ident #ref_ty_tys {
+                match self {
+                    #(Self::#variant_idents #patterns => #ident::#variant_idents #patterns,)*
+                }
             }
-            pub fn all_false(&self) -> bool {
-                !(false $(|| self.$ops)*)
+        });
+    }
+
+    let ref_ident = format_ident!("{input_ident}Ref");
+    let clone = quote! { ::core::clone::Clone::clone };
+    let owned = bindings.iter().map(|(_, names)| {
+        let names = names.iter();
+        quote! { #(#names: #clone(*#names)),* }
+    });
+    let owned = data
+        .variants
+        .iter()
+        .zip(owned)
+        .map(|(v, fields)| match &v.fields {
+            Fields::Named(_) => quote! { { #fields } },
+            // tuple fields can be given by index in braces too
+            Fields::Unnamed(f) => {
+                let indices = (0..f.unnamed.len()).map(syn::Index::from);
+                let names =
+                    (0..f.unnamed.len()).map(|i| Ident::new(&format!("f{i}"), Span::mixed_site()));
+                quote! { { #(#indices: #clone(*#names)),* } }
+            }
+            Fields::Unit => TokenStream::new(),
+        });
+    // the binder stops these counting as trivial bounds, which would be an
+    // error for fields which aren't `Clone` rather than just making this
+    // unavailable
+    let hrtb = fresh_lifetime(&generics);
+    let field_tys = data
+        .variants
+        .iter()
+        .flat_map(|v| v.fields.iter().map(|f| without_self(&f.ty, input)));
+    defs.extend(quote! {
+        #[automatically_derived]
+        impl #impl_tys ::core::clone::Clone for #ref_ident #ref_ty_tys #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+        #[automatically_derived]
+        impl #impl_tys ::core::marker::Copy for #ref_ident #ref_ty_tys #where_clause {}
+        #[automatically_derived]
+        impl #impl_tys #ref_ident #ref_ty_tys #where_clause {
+            /// Clone the borrowed field(s) into an owned enum
+            #vis fn to_owned_enum(&self) -> #input_ident #ty_tys
+            where
+                #(for<#hrtb> #field_tys: ::core::clone::Clone),*
+            {
+                match self {
+                    #(Self::#variant_idents #patterns => #input_ident::#variant_idents #owned,)*
+                }
             }
+        }
+    });
+    (defs, fns)
+}
 
-            pub fn all_false_but_is(&self) -> bool {
-                if self.all_false() {
-                    false
-                } else {
-                    let mut me = self.clone();
-                    me.add_is = false;
-                    me.all_false()
+/// Invoke the bridge macro from `#[delegate]` for each trait in `delegates`,
+/// which needs every variant to have a single field to forward to
+fn generate_delegates(
+    delegates: &[syn::Path],
+    input: &syn::DeriveInput,
+) -> syn::Result<Vec<TokenStream>> {
+    if delegates.is_empty() {
+        return Ok(Vec::new());
+    }
+    let variants = single_field_variants(input, "delegate")?;
+    let input_ident = &input.ident;
+    let (impl_tys, ty_tys, where_clause) = input.generics.split_for_impl();
+    let (vs, tys): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+    Ok(delegates
+        .iter()
+        .map(|path| {
+            // bounding the fields means generic enums don't need to repeat
+            // them, and gives a better error if a variant doesn't implement it
+            let predicates = where_clause
+                .iter()
+                .flat_map(|w| w.predicates.iter().map(|p| p.to_token_stream()))
+                .chain(tys.iter().map(|t| quote! { #t: #path }));
+            quote! {
+                #path! {
+                    @delegate impl [#impl_tys] [#path] for [#input_ident #ty_tys]
+                    where [#(#predicates),*]
+                    variants [#((#vs, #tys)),*]
                 }
             }
-            fn add_scope(mut self, attrs: &[Attribute]) -> syn::Result<Self> {
-                for attr in attrs {
-                    if attr.path().is_ident("sumtype") {
-                        attr.parse_nested_meta(|meta| {
-                            self.add_syn(&meta)?;
-                            Ok(())
-                        })?;
-                        return Ok(self);
-                    }
+        })
+        .collect())
+}
+
+/// `vis` as seen from inside a module declared next to the item, so that
+/// things in the module are visible to the same places as the item
+fn vis_in_child(vis: &syn::Visibility) -> syn::Visibility {
+    match vis {
+        syn::Visibility::Inherited => syn::parse_quote! { pub(super) },
+        syn::Visibility::Restricted(r) => {
+            let path = &r.path;
+            match path.segments.first() {
+                Some(first) if first.ident == "self" && path.segments.len() == 1 => {
+                    syn::parse_quote! { pub(super) }
                 }
-                Ok(self)
+                Some(first) if first.ident == "self" => {
+                    let rest = path.segments.iter().skip(1);
+                    syn::parse_quote! { pub(in super::#(#rest)::*) }
+                }
+                Some(first) if first.ident == "super" => {
+                    syn::parse_quote! { pub(in super::#path) }
+                }
+                // `crate` and absolute paths mean the same thing anywhere
+                _ => vis.clone(),
             }
         }
-    };
+        syn::Visibility::Public(_) => vis.clone(),
+    }
 }
 
-define_attrs!(Attrs {
-    (add_as, as),
-    (add_into, into),
-    (add_is, is),
-    (add_mut_as, as_mut),
-    (add_try_into, try_into),
-    (add_try_into_impl, impl_try_into),
-    (add_try_as, try_as),
-    (add_try_as_mut, try_as_mut),
-    (add_from_impl, from)
-});
-fn generate_conv_option<'a>(
-    vis: &'a syn::Visibility,
-    prefix: Option<TokenStream>,
-) -> impl FnOnce(&[&Ident], &[&syn::Type], &[Ident]) -> TokenStream + 'a {
-    move |variants: &[&Ident], tys: &[&syn::Type], names: &[Ident]| {
-        quote! {
-            #(
-                #vis fn #names (#prefix self) -> Option<#prefix #tys> {
-                    match self {
-                        Self::#variants (v) => Some(v),
-                        _ => None,
+/// The `{enum}_variants` module, with a marker type implementing `Variant`
+/// for each variant that has a single field
+fn generate_markers(krate: &syn::Path, input: &syn::DeriveInput) -> TokenStream {
+    let syn::Data::Enum(data) = &input.data else {
+        unreachable!()
+    };
+    let input_ident = &input.ident;
+    let vis = &input.vis;
+    let inner_vis = vis_in_child(vis);
+    let (impl_tys, ty_tys, where_clause) = input.generics.split_for_impl();
+    let module = format_ident!("{}_variants", input_ident.to_string().to_case(Case::Snake));
+    let value = Ident::new("value", Span::mixed_site());
+    let mut markers = Vec::new();
+    let mut impls = Vec::new();
+    for (index, v) in data.variants.iter().enumerate() {
+        let ident = &v.ident;
+        let inner = match &v.fields {
+            // `Self` would be the marker in its impl
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => without_self(&f.unnamed[0].ty, input),
+            _ => continue,
+        };
+        let doc = format!("Marker for [`{input_ident}::{ident}`](super::{input_ident}::{ident})");
+        markers.push(quote! {
+            #[doc = #doc]
+            #[derive(
+                ::core::fmt::Debug,
+                ::core::clone::Clone,
+                ::core::marker::Copy,
+                ::core::cmp::PartialEq,
+                ::core::cmp::Eq,
+                ::core::hash::Hash,
+                ::core::default::Default,
+            )]
+            #inner_vis struct #ident;
+        });
+        let name = ident.to_string();
+        impls.push(quote! {
+            #[automatically_derived]
+            impl #impl_tys #krate::Variant<#input_ident #ty_tys> for #module::#ident #where_clause {
+                type Inner = #inner;
+                const NAME: &'static str = #name;
+                const INDEX: usize = #index;
+                fn get(e: &#input_ident #ty_tys) -> ::core::option::Option<&Self::Inner> {
+                    match e {
+                        #input_ident::#ident(#value) => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
                     }
                 }
-            )*
+                fn get_mut(e: &mut #input_ident #ty_tys) -> ::core::option::Option<&mut Self::Inner> {
+                    match e {
+                        #input_ident::#ident(#value) => ::core::option::Option::Some(#value),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+                fn into_inner(e: #input_ident #ty_tys) -> ::core::result::Result<Self::Inner, #input_ident #ty_tys> {
+                    match e {
+                        #input_ident::#ident(#value) => ::core::result::Result::Ok(#value),
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+                fn wrap(#value: Self::Inner) -> #input_ident #ty_tys {
+                    #input_ident::#ident(#value)
+                }
+            }
+        });
+    }
+    let doc = format!("Marker types for the variants of [`{input_ident}`]");
+    quote! {
+        #[doc = #doc]
+        #vis mod #module {
+            #(#markers)*
         }
+        #(#impls)*
     }
 }
-fn generate_failed_matches<'a>(
-    variants: &[FullVariant<'a>],
-    input_ident: &Ident,
-    wanted: impl ToTokens,
-) -> TokenStream {
-    let patterns = variants.iter().map(|v| v.match_pat());
-    let names = variants.iter().map(|v| v.name());
-    quote! {
-        #(
-            #input_ident :: #patterns => Err(::typesum::TryIntoError::new(stringify!(#input_ident), stringify!(#names), #wanted))
-        ),*
+
+/// Whether `ty` is the enum itself, written as `Self` or by its name
+fn is_node(ty: &syn::Type, input_ident: &Ident) -> bool {
+    matches!(ty, syn::Type::Path(p)
+        if p.qself.is_none()
+            && p.path.segments.len() == 1
+            && (p.path.segments[0].ident == "Self" || p.path.segments[0].ident == *input_ident))
+}
+
+/// The type inside `ty` if it's `wrapper<T>`, going by the last segment of
+/// the path since that's all we have to go on
+fn wrapped<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+    let syn::Type::Path(p) = ty else {
+        return None;
+    };
+    let last = p.path.segments.last()?;
+    if last.ident != wrapper {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(t) => Some(t),
+        _ => None,
     }
 }
 
-/// Generate all the match blocks (match `on` { ... }) where each
-/// variant is matched and all others are errors
-fn generate_try_match_blocks<'a>(
-    variants: &'a [&'a Ident],
-    input_ident: &'a Ident,
-    all_variants: &'a [FullVariant],
-) -> impl Iterator<Item = TokenStream> + 'a {
-    variants.iter().map(move |v| {
-        let failed = generate_failed_matches(all_variants, input_ident, quote! { stringify!(#v) });
+/// An error for a field type which holds nodes `fold` can't get at, since
+/// skipping them would quietly leave part of the tree unwalked
+fn unwalkable(ty: &syn::Type, input_ident: &Ident) -> syn::Result<Option<TokenStream>> {
+    let mentions = Mentions::of(ty);
+    if mentions.idents.contains("Self") || mentions.idents.contains(&input_ident.to_string()) {
+        Err(syn::Error::new_spanned(
+            ty,
+            "`fold` can only walk into the enum through `Box`, `Vec`, `Option`, tuples and arrays",
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
+/// `value`, of type `ty`, with every node in it passed through `fold`. `None`
+/// if there aren't any nodes in it
+fn fold_field(
+    ty: &syn::Type,
+    value: TokenStream,
+    input_ident: &Ident,
+    fold: &TokenStream,
+) -> syn::Result<Option<TokenStream>> {
+    let x = Ident::new("x", Span::mixed_site());
+    Ok(Some(if is_node(ty, input_ident) {
+        quote! { #fold(self, #value) }
+    } else if let Some(inner) = wrapped(ty, "Box") {
+        // folding in place keeps the allocation and a copy of the node off
+        // the stack
+        let Some(inner) = fold_field(inner, quote! { *#x }, input_ident, fold)? else {
+            return Ok(None);
+        };
+        quote! {{
+            let mut #x = #value;
+            *#x = #inner;
+            #x
+        }}
+    } else if let Some(inner) = wrapped(ty, "Vec") {
+        let Some(inner) = fold_field(inner, quote! { #x }, input_ident, fold)? else {
+            return Ok(None);
+        };
         quote! {
-            match self {
-                #input_ident :: #v(v) => Ok(v),
-                #failed
+            ::core::iter::Iterator::collect(
+                ::core::iter::Iterator::map(::core::iter::IntoIterator::into_iter(#value), |#x| #inner)
+            )
+        }
+    } else if let Some(inner) = wrapped(ty, "Option") {
+        let Some(inner) = fold_field(inner, quote! { #x }, input_ident, fold)? else {
+            return Ok(None);
+        };
+        quote! { ::core::option::Option::map(#value, |#x| #inner) }
+    } else if let syn::Type::Array(array) = ty {
+        let Some(inner) = fold_field(&array.elem, quote! { #x }, input_ident, fold)? else {
+            return Ok(None);
+        };
+        quote! { (#value).map(|#x| #inner) }
+    } else if let syn::Type::Tuple(tuple) = ty {
+        let bound = (0..tuple.elems.len())
+            .map(|i| Ident::new(&format!("x{i}"), Span::mixed_site()))
+            .collect::<Vec<_>>();
+        let mut any = false;
+        let mut values = Vec::new();
+        for (elem, b) in tuple.elems.iter().zip(&bound) {
+            let folded = fold_field(elem, quote! { #b }, input_ident, fold)?;
+            any |= folded.is_some();
+            values.push(folded.unwrap_or_else(|| quote! { #b }));
+        }
+        if !any {
+            return Ok(None);
+        }
+        quote! {{
+            let (#(#bound,)*) = #value;
+            (#(#values,)*)
+        }}
+    } else {
+        return unwalkable(ty, input_ident);
+    }))
+}
+
+/// Statement which passes every node in `value`, a reference to a `ty`,
+/// to `visit`. `None` if there aren't any nodes in it
+fn visit_field(
+    ty: &syn::Type,
+    value: TokenStream,
+    input_ident: &Ident,
+    visit: &TokenStream,
+) -> syn::Result<Option<TokenStream>> {
+    let x = Ident::new("x", Span::mixed_site());
+    Ok(Some(if is_node(ty, input_ident) {
+        quote! { #visit(self, #value); }
+    } else if let Some(inner) = wrapped(ty, "Box") {
+        return visit_field(inner, quote! { &**#value }, input_ident, visit);
+    } else if let Some(inner) = wrapped(ty, "Vec") {
+        let Some(inner) = visit_field(inner, quote! { #x }, input_ident, visit)? else {
+            return Ok(None);
+        };
+        quote! { for #x in #value { #inner } }
+    } else if let Some(inner) = wrapped(ty, "Option") {
+        let Some(inner) = visit_field(inner, quote! { #x }, input_ident, visit)? else {
+            return Ok(None);
+        };
+        quote! { if let ::core::option::Option::Some(#x) = #value { #inner } }
+    } else if let syn::Type::Array(array) = ty {
+        let Some(inner) = visit_field(&array.elem, quote! { #x }, input_ident, visit)? else {
+            return Ok(None);
+        };
+        quote! { for #x in #value { #inner } }
+    } else if let syn::Type::Tuple(tuple) = ty {
+        let mut bound = Vec::new();
+        let mut walks = Vec::new();
+        for (i, elem) in tuple.elems.iter().enumerate() {
+            let b = Ident::new(&format!("x{i}"), Span::mixed_site());
+            match visit_field(elem, quote! { #b }, input_ident, visit)? {
+                Some(walk) => {
+                    bound.push(b.into_token_stream());
+                    walks.push(walk);
+                }
+                None => bound.push(quote! { _ }),
             }
         }
-    })
+        if walks.is_empty() {
+            return Ok(None);
+        }
+        quote! {{
+            let (#(#bound,)*) = #value;
+            #(#walks)*
+        }}
+    } else {
+        return unwalkable(ty, input_ident);
+    }))
 }
 
-fn generate_conv_try<'a>(
-    vis: &'a syn::Visibility,
-    input_ident: &'a Ident,
-    prefix: Option<TokenStream>,
-    all_variants: &'a [FullVariant],
-    input_tys: &'a syn::Generics,
-) -> impl FnOnce(&[&Ident], &[&syn::Type], &[Ident]) -> TokenStream + 'a {
-    move |variants, tys, names| {
-        let blocks = generate_try_match_blocks(variants, input_ident, all_variants);
-        quote! {
+/// The `{Enum}Fold` and `{Enum}Visit` traits, which have a method for each
+/// variant (named after `names`) that by default walks into any fields
+/// holding more of the enum
+fn generate_fold(
+    vis: &syn::Visibility,
+    naming: &Naming,
+    input: &syn::DeriveInput,
+    names: &[String],
+) -> syn::Result<TokenStream> {
+    let syn::Data::Enum(data) = &input.data else {
+        unreachable!()
+    };
+    let input_ident = &input.ident;
+    let generics = &input.generics;
+    let where_clause = &generics.where_clause;
+    let (_, ty_tys, _) = generics.split_for_impl();
+    let node_ty = quote! { #input_ident #ty_tys };
+    let node_name = naming.variant(input_ident);
+    for (i, (v, name)) in data.variants.iter().zip(names).enumerate() {
+        if *name == node_name {
+            return Err(syn::Error::new_spanned(
+                v,
+                format!("`fold_{name}` is already generated for the whole enum, rename `{}` with #[sumtype(rename = \"...\")]", v.ident),
+            ));
+        }
+        if let Some(other) = data
+            .variants
+            .iter()
+            .zip(names)
+            .take(i)
+            .find(|(_, n)| *n == name)
+        {
+            return Err(syn::Error::new_spanned(
+                v,
+                format!("`fold_{name}` would be generated for both `{}` and `{}`, rename one of them with #[sumtype(rename = \"...\")]", other.0.ident, v.ident),
+            ));
+        }
+    }
+    let fold_trait = format_ident!("{input_ident}Fold");
+    let visit_trait = format_ident!("{input_ident}Visit");
+    let fold_node = format_ident!("fold_{node_name}");
+    let visit_node = format_ident!("visit_{node_name}");
+    let fold_variants = names
+        .iter()
+        .map(|n| format_ident!("fold_{n}"))
+        .collect::<Vec<_>>();
+    let visit_variants = names
+        .iter()
+        .map(|n| format_ident!("visit_{n}"))
+        .collect::<Vec<_>>();
+    let fold = quote! { <Self as #fold_trait #ty_tys>::#fold_node };
+    let visit = quote! { <Self as #visit_trait #ty_tys>::#visit_node };
+    let node = Ident::new("node", Span::mixed_site());
+
+    let mut patterns = Vec::new();
+    let mut args = Vec::new();
+    let mut params = Vec::new();
+    let mut ref_params = Vec::new();
+    let mut folded = Vec::new();
+    let mut visited = Vec::new();
+    for v in &data.variants {
+        let bound = match &v.fields {
+            Fields::Named(f) => f.named.iter().map(|f| f.ident.clone().unwrap()).collect(),
+            Fields::Unnamed(f) => (0..f.unnamed.len())
+                .map(|i| Ident::new(&format!("f{i}"), Span::mixed_site()))
+                .collect(),
+            Fields::Unit => Vec::new(),
+        };
+        let ident = &v.ident;
+        patterns.push(match &v.fields {
+            Fields::Named(_) => quote! { { #(#bound),* } },
+            Fields::Unnamed(_) => quote! { ( #(#bound),* ) },
+            Fields::Unit => TokenStream::new(),
+        });
+        args.push(quote! { #(#bound),* });
+        let tys = v
+            .fields
+            .iter()
+            .map(|f| replace_self(f.ty.to_token_stream(), &node_ty))
+            .collect::<Vec<_>>();
+        params.push(quote! { #(#bound: #tys),* });
+        ref_params.push(quote! { #(#bound: &#tys),* });
+        let mut values = Vec::new();
+        let mut walks = Vec::new();
+        for (f, b) in v.fields.iter().zip(&bound) {
+            values.push(
+                fold_field(&f.ty, quote! { #b }, input_ident, &fold)?
+                    .unwrap_or_else(|| quote! { #b }),
+            );
+            walks.extend(visit_field(&f.ty, quote! { #b }, input_ident, &visit)?);
+        }
+        folded.push(match &v.fields {
+            Fields::Named(_) => quote! { #input_ident::#ident { #(#bound: #values),* } },
+            Fields::Unnamed(_) => quote! { #input_ident::#ident(#(#values),*) },
+            Fields::Unit => quote! { #input_ident::#ident },
+        });
+        visited.push(quote! { #(#walks)* });
+    }
+    let variant_idents = data.variants.iter().map(|v| &v.ident).collect::<Vec<_>>();
+    let fold_doc = format!("Rebuilds a [`{input_ident}`] from the bottom up. Every method walks into the fields holding more of the enum by default, so only the variants of interest need to be overridden");
+    let visit_doc = format!("Walks a [`{input_ident}`] depth first. Every method walks into the fields holding more of the enum by default, so only the variants of interest need to be overridden");
+    Ok(quote! {
+        #[doc = #fold_doc]
+        #vis trait #fold_trait #generics #where_clause {
+            fn #fold_node(&mut self, #node: #node_ty) -> #node_ty {
+                match #node {
+                    #(#input_ident::#variant_idents #patterns => <Self as #fold_trait #ty_tys>::#fold_variants(self, #args),)*
+                }
+            }
             #(
-                #vis fn #names (#prefix self) -> ::core::result::Result<#prefix #tys, ::typesum::TryIntoError<#input_ident #input_tys>> {
-                    #blocks
+                fn #fold_variants(&mut self, #params) -> #node_ty {
+                    #folded
                 }
             )*
         }
-    }
+        #[doc = #visit_doc]
+        #vis trait #visit_trait #generics #where_clause {
+            fn #visit_node(&mut self, #node: &#node_ty) {
+                match #node {
+                    #(#input_ident::#variant_idents #patterns => <Self as #visit_trait #ty_tys>::#visit_variants(self, #args),)*
+                }
+            }
+            #(
+                #[allow(unused_variables, clippy::ptr_arg)]
+                fn #visit_variants(&mut self, #ref_params) {
+                    #visited
+                }
+            )*
+        }
+    })
 }
 
-fn gen_names<'a, 'b, A: 'a, B: 'a, C: 'a, R>(
-    names: impl Iterator<Item = &'a (&'a (C, impl std::fmt::Display + 'a), (&'a A, &'a B))> + 'b,
-    prefix: &str,
-    suffix: Option<&str>,
-    filter: impl Fn(&C) -> bool,
+fn gen_names<'a, 'b, A: 'a, B: 'a, R>(
+    names: impl Iterator<Item = &'a (&'a (Attrs, impl std::fmt::Display + 'a), (&'a A, &'a B))> + 'b,
+    naming: &Naming,
+    family: &Family,
     generate: impl FnOnce(&[&A], &[&B], &[Ident]) -> R,
 ) -> R
 where
     'b: 'a,
 {
     let (as_, bs, is) = names
-        .filter(|((a, _), _)| filter(a))
-        .map(|((_, n), (a, b))| {
-            (
-                a,
-                b,
-                Ident::new(
-                    &format!(
-                        "{}_{}{}",
-                        prefix,
-                        n,
-                        suffix
-                            .map(|s| format!("_{s}"))
-                            .unwrap_or_else(|| String::new())
-                    ),
-                    Span::call_site(),
-                ),
-            )
-        })
+        .filter(|((a, _), _)| (family.enabled)(a))
+        .map(|((_, n), (a, b))| (a, b, naming.method(family, &n.to_string())))
         .fold(
             (Vec::new(), Vec::new(), Vec::new()),
             |(mut as_, mut bs, mut is), (a, b, i)| {
@@ -217,82 +2171,249 @@ where
         );
     generate(as_.as_slice(), bs.as_slice(), is.as_slice())
 }
+/// Make sure every generated function has a unique name, since otherwise you
+/// get a fairly unhelpful error pointing into the macro output
+fn check_name_collisions(
+    naming: &Naming,
+    names: &[(Attrs, String)],
+    variants: &[Ident],
+    fixed: &[&str],
+) -> syn::Result<()> {
+    let mut generated: Vec<(Ident, &Ident)> = Vec::new();
+    for ((attrs, name), variant) in names.iter().zip(variants) {
+        for family in FAMILIES.iter().filter(|f| (f.enabled)(attrs)) {
+            let method = naming.method(family, name);
+            if fixed.iter().any(|f| method == f) {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("`{method}` is already generated for the whole enum, rename `{variant}` with #[sumtype(rename = \"...\")]"),
+                ));
+            }
+            if let Some((_, other)) = generated.iter().find(|(g, _)| *g == method) {
+                let msg = if *other == variant {
+                    format!("`{method}` would be generated more than once for `{variant}`, change the prefix or suffix of one of them")
+                } else {
+                    format!("`{method}` would be generated for both `{other}` and `{variant}`, rename one of them with #[sumtype(rename = \"...\")]")
+                };
+                return Err(syn::Error::new_spanned(variant, msg));
+            }
+            generated.push((method, variant));
+        }
+    }
+    Ok(())
+}
+
+/// Same as [`check_name_collisions`] but for the view structs, whose names
+/// come from the variants themselves so renaming doesn't help
+fn check_type_collisions(views: &[(Ident, &Ident)], fixed: &[Ident]) -> syn::Result<()> {
+    for (i, (ty, variant)) in views.iter().enumerate() {
+        if fixed.contains(ty) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("`{ty}` is already generated for the whole enum, so `{variant}` can't have a view struct by that name"),
+            ));
+        }
+        if let Some((_, other)) = views[..i].iter().find(|(t, _)| t == ty) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("`{ty}` would be generated as a view struct for both `{other}` and `{variant}`, rename one of the variants"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `meta` is `only = name`, without parsing it
+fn is_only(meta: &syn::meta::ParseNestedMeta, name: &str) -> bool {
+    let fork = meta.input.fork();
+    meta.path.is_ident("only")
+        && fork.parse::<Token![=]>().is_ok()
+        && fork.parse::<Ident>().is_ok_and(|i| i == name)
+}
+
 const EXPLICITLY_DISABLE_FROM_MSG: &str = "You need to explicitly disable the ones you don't want with #[sumtype(from = false)]. See the docs on #[sumtype] for more information";
 
-pub fn sumtype_attr(mut attrs: Attrs, input: syn::DeriveInput) -> TokenStream {
+pub fn sumtype_attr(attrs: Attrs, options: Options, input: syn::DeriveInput) -> TokenStream {
     let syn::Data::Enum(data) = &input.data else {
         return syn::Error::new_spanned(&input, "sumtype can only act on enums").to_compile_error();
     };
     let input_ident = &input.ident;
     let vis = &input.vis;
     let tys = &input.generics;
-    attrs.add_try_into_impl &= input.generics.type_params().next().is_none();
+    let (impl_tys, ty_tys, where_clause) = tys.split_for_impl();
     let mut variant_names = Vec::new();
+    let mut method_names = Vec::new();
+    let mut is_matches = Vec::new();
     let mut variants = Vec::new();
     let mut variant_tys = Vec::new();
+    let mut views = Vec::new();
+    let mut view_types = Vec::new();
+    let mut errors = CustomErrors::new();
+    let mut ref_variant_args = Vec::new();
+    let mut fold_names = Vec::new();
+    let match_with = attrs.add_match_with;
+    let failure = Failure {
+        krate: &options.krate,
+        keep_value: options.keep_value,
+    };
     let all_variant_matches = data
         .variants
         .iter()
         .map(|v| FullVariant { inner: v })
         .collect::<Vec<_>>();
-    for variant in &data.variants {
-        let attrs = attrs.add_scope(&variant.attrs);
+    for (variant, full) in data.variants.iter().zip(&all_variant_matches) {
+        let mut rename = None;
+        let mut error = None;
+        let attrs = attrs.add_scope(&variant.attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(parse_name_part(meta, false)?);
+                Ok(true)
+            } else if meta.path.is_ident("error") {
+                error = Some(meta.value()?.parse()?);
+                Ok(true)
+            } else if meta.path.is_ident("match_with") || is_only(meta, "match_with") {
+                Err(meta.error(
+                    "`match_with` can only be set on the whole enum, \
+                     since `fold` needs a closure for every variant",
+                ))
+            } else {
+                Ok(false)
+            }
+        });
         if let Err(e) = attrs {
             return e.to_compile_error();
         }
-        let attrs = attrs.unwrap();
+        let mut attrs = attrs.unwrap();
+        // the closure in `fold` is all a unit variant can have besides `is`
+        if match_with && matches!(variant.fields, Fields::Unit) {
+            attrs = attrs.for_unit();
+        }
+        // `fold` has to take a closure for every variant to be exhaustive,
+        // so it's up to the enum whether there is one
+        attrs.add_match_with = match_with;
+        fold_names.push(
+            rename
+                .clone()
+                .unwrap_or_else(|| options.naming.variant(&variant.ident)),
+        );
+        ref_variant_args.push(if attrs.all_false() {
+            quote! { ignore }
+        } else {
+            let args = attrs.to_args();
+            let rename = rename.as_ref().map(|r| quote! { , rename = #r });
+            quote! { #args #rename }
+        });
         if attrs.all_false() {
             continue;
         }
+        if let Some(error) = error.or_else(|| options.error.clone()) {
+            errors.insert(variant.ident.clone(), error);
+        }
         variant_names.push(variant.ident.clone());
+        method_names.push(rename.unwrap_or_else(|| options.naming.variant(&variant.ident)));
+        is_matches.push(*full);
         variants.push((attrs, variant.ident.clone()));
         if attrs.all_false_but_is() {
-            variant_tys.push(syn::Type::Never(syn::TypeNever {
+            variant_tys.push(Payload::Single(syn::Type::Never(syn::TypeNever {
                 bang_token: Not::default(),
-            }));
+            })));
             continue;
         }
-        let Fields::Unnamed(f) = &variant.fields else {
-            return syn::Error::new_spanned(variant, "must be single variant").to_compile_error();
-        };
-        if f.unnamed.len() != 1 {
-            return syn::Error::new_spanned(f, "must be single variant").to_compile_error();
+        match &variant.fields {
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => {
+                let ty = &f.unnamed.first().unwrap().ty;
+                variant_tys.push(Payload::Single(without_self(ty, &input)));
+            }
+            Fields::Named(f) => {
+                let named = NamedPayload::new(&input, variant, f);
+                for access in [Access::Owned, Access::Ref, Access::Mut] {
+                    if attrs.uses_access(access) {
+                        views.push(named.definition(vis, input_ident, &variant.ident, access));
+                        view_types.push((named.ident(access).clone(), &variant.ident));
+                    }
+                }
+                variant_tys.push(Payload::Named(named));
+            }
+            Fields::Unnamed(f) => {
+                variant_tys.push(Payload::Tuple(
+                    f.unnamed
+                        .iter()
+                        .map(|f| without_self(&f.ty, &input))
+                        .collect(),
+                ));
+            }
+            Fields::Unit if match_with => variant_tys.push(Payload::Unit),
+            Fields::Unit => {
+                return syn::Error::new_spanned(variant, "must be single variant")
+                    .to_compile_error();
+            }
         }
-        variant_tys.push(f.unnamed.first().unwrap().ty.to_owned());
     }
-    if variants.is_empty() {
+    if variants.is_empty()
+        && options.delegates.is_empty()
+        && options.as_dyn.is_none()
+        && options.ref_enum.is_none()
+        && !options.fold
+        && !options.markers
+    {
         return quote! {
-            compile_error!("this sumtype annotation won't do anything, try adding some options like #[sumtype(all = false, is = true)]");
-        }.into();
+            compile_error!("this sumtype annotation won't do anything, \
+                try adding some options like #[sumtype(all = false, is = true)]");
+        }
+        .into();
     }
     let lowercase_names = variants
         .iter()
-        .map(|(a, v)| (a.clone(), v.to_string().to_case(Case::Snake)))
+        .zip(method_names)
+        .map(|((a, _), n)| (*a, n))
         .collect::<Vec<_>>();
     let variants_zipped = lowercase_names
         .iter()
         .zip(variant_names.iter().zip(variant_tys.iter()))
         .collect::<Vec<_>>();
+    let mut fixed_names = options.fixed_names();
+    if variants.iter().any(|(a, _)| a.add_has) {
+        fixed_names.extend(["get", "get_mut", "take", "inject"]);
+    }
+    if attrs.add_match_with {
+        fixed_names.extend(["fold", "fold_ref", "fold_mut"]);
+    }
+    if variants.iter().any(|(a, _)| a.add_visitor) {
+        fixed_names.extend(["accept", "accept_mut", "accept_owned", "visit_other"]);
+    }
+    if let Err(e) = check_name_collisions(
+        &options.naming,
+        &lowercase_names,
+        &variant_names,
+        &fixed_names,
+    ) {
+        return e.to_compile_error();
+    }
+    let mut fixed_types = options.fixed_types(input_ident);
+    if variants.iter().any(|(a, _)| a.add_visitor) {
+        fixed_types.extend(["", "Mut", "Owned"].map(|s| format_ident!("{input_ident}Visitor{s}")));
+    }
+    if let Err(e) = check_type_collisions(&view_types, &fixed_types) {
+        return e.to_compile_error();
+    }
     let as_names = gen_names(
         variants_zipped.iter(),
-        "as",
-        None,
-        |a| a.add_as,
-        generate_conv_option(vis, Some(quote! { & })),
+        &options.naming,
+        &AS,
+        generate_conv_option(vis, Access::Ref),
     );
     let into_names = gen_names(
         variants_zipped.iter(),
-        "into",
-        None,
-        |a| a.add_into,
-        generate_conv_option(vis, None),
+        &options.naming,
+        &INTO,
+        generate_conv_option(vis, Access::Owned),
     );
-    let is_names = all_variant_matches
+    let is_names = is_matches
         .iter()
         .zip(lowercase_names.iter())
-        .filter(|(_, (a, _))| a.add_is)
+        .filter(|(_, (a, _))| (IS.enabled)(a))
         .map(|(f, (_, name))| {
-            let name = format_ident!("is_{name}");
+            let name = options.naming.method(&IS, name);
             let case = f.match_pat();
             quote! {
                 #vis fn #name (&self) -> bool {
@@ -305,59 +2426,140 @@ pub fn sumtype_attr(mut attrs: Attrs, input: syn::DeriveInput) -> TokenStream {
         });
     let mut_as_names = gen_names(
         variants_zipped.iter(),
-        "as",
-        Some("mut"),
-        |a| a.add_mut_as,
-        generate_conv_option(vis, Some(quote! { &mut })),
+        &options.naming,
+        &AS_MUT,
+        generate_conv_option(vis, Access::Mut),
     );
     let try_into_names = gen_names(
         variants_zipped.iter(),
-        "try_into",
-        None,
-        |a| a.add_try_into,
-        generate_conv_try(vis, input_ident, None, &all_variant_matches, &tys),
+        &options.naming,
+        &TRY_INTO,
+        generate_conv_try(vis, input_ident, Access::Owned, &ty_tys, failure, &errors),
     );
 
     let try_as_impls = gen_names(
         variants_zipped.iter(),
-        "try_as",
-        None,
-        |a| a.add_try_as,
+        &options.naming,
+        &TRY_AS,
         generate_conv_try(
             vis,
             input_ident,
-            Some(quote! { & }),
-            &all_variant_matches,
-            &tys,
+            Access::Ref,
+            &ty_tys,
+            failure.without_value(),
+            &errors,
         ),
     );
 
     let try_as_mut_impls = gen_names(
         variants_zipped.iter(),
-        "try_as",
-        Some("mut"),
-        |a| a.add_try_as,
+        &options.naming,
+        &TRY_AS_MUT,
         generate_conv_try(
             vis,
             input_ident,
-            Some(quote! { &mut }),
-            &all_variant_matches,
-            &tys,
+            Access::Mut,
+            &ty_tys,
+            failure.without_value(),
+            &errors,
         ),
     );
-    let try_intos = variants
-        .iter()
-        .zip(variant_tys.iter())
-        .filter(|((a, _), _)| a.add_try_into_impl)
-        .map(|((_, v), ty)| (ty, v))
-        .collect::<Vec<_>>();
+    let map_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &MAP,
+        generate_map(vis),
+    );
+    let map_into_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &MAP_INTO,
+        generate_map_into(vis, tys),
+    );
+    let and_then_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &AND_THEN,
+        generate_and_then(vis),
+    );
+    let inspect_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &INSPECT,
+        generate_inspect(vis),
+    );
+    let unwrap_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &UNWRAP,
+        generate_unwrap(vis, input_ident, Access::Owned, false, failure),
+    );
+    let unwrap_ref_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &UNWRAP_REF,
+        generate_unwrap(vis, input_ident, Access::Ref, false, failure),
+    );
+    let expect_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &EXPECT,
+        generate_unwrap(vis, input_ident, Access::Owned, true, failure),
+    );
+    let or_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &OR,
+        generate_or(vis, false),
+    );
+    let or_else_impls = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &OR_ELSE,
+        generate_or(vis, true),
+    );
+    // TryFrom gives us TryInto through the blanket impl, so implementing both
+    // would conflict
+    let try_intos = match conversion_buckets(
+        &variants,
+        &variant_tys,
+        input_ident,
+        tys,
+        Access::Owned,
+        &errors,
+        "impl_try_into",
+        |a| a.add_try_into_impl && !a.add_try_from_impl,
+    ) {
+        Ok(b) => b,
+        Err(e) => return e.to_compile_error(),
+    };
+    let try_froms = match conversion_buckets(
+        &variants,
+        &variant_tys,
+        input_ident,
+        tys,
+        Access::Owned,
+        &errors,
+        "impl_try_from",
+        |a| a.add_try_from_impl,
+    ) {
+        Ok(b) => b,
+        Err(e) => return e.to_compile_error(),
+    };
+    // named variants don't have a single type that could be converted from, and
+    // tuples would be a bit surprising so they have to be asked for
     let from_candidates = variants_zipped
         .iter()
-        .filter(|((a, _), _)| a.add_from_impl)
+        .filter(|((a, _), (_, p))| match p {
+            Payload::Single(_) => a.add_from_impl,
+            Payload::Tuple(_) => a.add_from_tuple_impl,
+            Payload::Named(_) | Payload::Unit => false,
+        })
+        .map(|(_, (v, p))| (v, p.owned_ty(), p))
         .collect::<Vec<_>>();
     let mut seen_from = Vec::new();
     let mut seen_generics = 0;
-    for (_, (_, candidate)) in &from_candidates {
+    for (_, candidate, _) in &from_candidates {
         if let syn::Type::Path(p) = candidate {
             if tys
                 .type_params()
@@ -366,32 +2568,98 @@ pub fn sumtype_attr(mut attrs: Attrs, input: syn::DeriveInput) -> TokenStream {
             {
                 seen_generics += 1;
                 if seen_generics > 1 {
-                    return syn::Error::new_spanned(candidate, format!("Multiple generic's with From implementations found, these will conflict. {EXPLICITLY_DISABLE_FROM_MSG}")).to_compile_error();
+                    return syn::Error::new_spanned(
+                        candidate,
+                        format!(
+                            "Multiple generic's with From implementations found, \
+                             these will conflict. {EXPLICITLY_DISABLE_FROM_MSG}"
+                        ),
+                    )
+                    .to_compile_error();
                 }
             }
         }
 
-        if seen_from.contains(candidate) {
+        if seen_from.contains(&candidate) {
             return syn::Error::new_spanned(
                 candidate,
-                "Multiple valid From candidates found. {EXPLICITLY_DISABLE_FROM_MSG}",
+                format!("Multiple valid From candidates found. {EXPLICITLY_DISABLE_FROM_MSG}"),
             )
             .to_compile_error();
         } else {
-            seen_from.push(&candidate);
+            seen_from.push(candidate);
         }
     }
-    let from_impls = from_candidates.into_iter().map(|((_, _), (v, t))| {
+    let from_impls = from_candidates.iter().map(|(v, t, p)| {
+        let value = p.value(Access::Owned);
+        let pattern = p.pattern();
         quote! {
            #[automatically_derived]
-           impl #tys ::core::convert::From<#t> for #input_ident #tys {
-               fn from(value: #t) -> Self {
-                   Self::#v (value)
+           impl #impl_tys ::core::convert::From<#t> for #input_ident #ty_tys #where_clause {
+               fn from(#value: #t) -> Self {
+                   Self::#v #pattern
                }
            }
         }
     });
 
+    let delegate_impls = match generate_delegates(&options.delegates, &input) {
+        Ok(d) => d,
+        Err(e) => return e.to_compile_error(),
+    };
+    let ref_enums = options
+        .ref_enum
+        .as_ref()
+        .map(|r| generate_ref_enums(attrs, &options, r, &input, &ref_variant_args));
+    let (ref_enums_defs, ref_enums_fns) = ref_enums.unzip();
+    let visited = variants.iter().filter(|(a, _)| a.add_visitor).count();
+    let (visitor_defs, visitor_fns) = gen_names(
+        variants_zipped.iter(),
+        &options.naming,
+        &VISIT,
+        generate_visitors(vis, &input, visited < data.variants.len()),
+    );
+    let fold_defs = if options.fold {
+        match generate_fold(vis, &options.naming, &input, &fold_names) {
+            Ok(d) => Some(d),
+            Err(e) => return e.to_compile_error(),
+        }
+    } else {
+        None
+    };
+    let match_with_fns = if attrs.add_match_with {
+        let (names, (variants, payloads)): (Vec<_>, (Vec<_>, Vec<_>)) = variants_zipped
+            .iter()
+            .map(|((_, n), (v, p))| (n, (*v, *p)))
+            .unzip();
+        Some(generate_match_with(vis, tys, &variants, &payloads, &names))
+    } else {
+        None
+    };
+    // only variants with a single field have something to hand out
+    let has_variants = variants_zipped
+        .iter()
+        .filter(|((a, _), _)| a.add_has)
+        .filter_map(|(_, (v, p))| match p {
+            Payload::Single(ty) => {
+                let index = data.variants.iter().position(|d| d.ident == **v).unwrap();
+                Some((*v, ty, index))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let (has_impls, has_fns) = generate_has(vis, &options.krate, &input, &has_variants);
+    let markers = options
+        .markers
+        .then(|| generate_markers(&options.krate, &input));
+    let as_dyn_impls = match &options.as_dyn {
+        Some(bounds) => match generate_as_dyn(vis, bounds, &input) {
+            Ok(d) => Some(d),
+            Err(e) => return e.to_compile_error(),
+        },
+        None => None,
+    };
+
     let mut minput = input.clone();
     let syn::Data::Enum(minput_data) = &mut minput.data else {
         unreachable!()
@@ -407,36 +2675,123 @@ pub fn sumtype_attr(mut attrs: Attrs, input: syn::DeriveInput) -> TokenStream {
     let input_stripped = quote! {
         #minput
     };
-    let try_into_impls = bucketise_by(|l, r| l == r, try_intos.into_iter())
-        .into_iter()
-        .map(|(ty, idents)| {
-            let name = idents
-                .iter()
-                .map(|i| i.to_string())
-                .reduce(|xs, x| format!("{xs} | {x}"))
-                .unwrap();
-            let failed = generate_failed_matches(&all_variant_matches, input_ident, &name);
+    let krate = &options.krate;
+    let names = data.variants.iter().map(|v| v.ident.to_string());
+    let patterns = all_variant_matches.iter().map(|v| v.match_pat());
+    let indices = 0..data.variants.len();
+    let sum_type_impl = quote! {
+        #[automatically_derived]
+        impl #impl_tys #krate::SumType for #input_ident #ty_tys #where_clause {
+            const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+            fn variant_index(&self) -> usize {
+                match self {
+                    #(Self::#patterns => #indices,)*
+                }
+            }
+        }
+    };
+    let owned_error = failure.error_ty(input_ident, &ty_tys);
+    let try_into_impls = try_intos.iter().map(|(ty, variants)| {
+        let arms = generate_bucket_arms(variants, Access::Owned, input_ident, failure);
+        let (error, body) = convert_error(
+            quote! { match self { #arms } },
+            &owned_error,
+            errors.get(variants[0].0),
+        );
+        quote! {
+            #[automatically_derived]
+            impl #impl_tys ::core::convert::TryInto<#ty> for #input_ident #ty_tys #where_clause {
+                type Error = #error;
+                fn try_into(self) -> ::core::result::Result<#ty, Self::Error> {
+                    #body
+                }
+            }
+        }
+    });
+    let try_from_ref_impls = match conversion_buckets(
+        &variants,
+        &variant_tys,
+        input_ident,
+        tys,
+        Access::Ref,
+        &errors,
+        "impl_try_from_ref",
+        |a| a.add_try_from_ref_impl,
+    ) {
+        Ok(b) => b,
+        Err(e) => return e.to_compile_error(),
+    };
+    let lifetime = fresh_lifetime(tys);
+    let mut ref_tys = tys.clone();
+    ref_tys.params.insert(
+        0,
+        syn::GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())),
+    );
+    let (ref_impl_tys, _, _) = ref_tys.split_for_impl();
+    let try_from_ref_impls = try_from_ref_impls
+        .iter()
+        .flat_map(|b| [(Access::Ref, b), (Access::Mut, b)])
+        .map(|(access, (_, variants))| {
+            let arms = generate_bucket_arms(variants, access, input_ident, failure.without_value());
+            let ty = variants[0].1.ty_with_lifetime(access, Some(&lifetime));
+            let borrow = access.with_lifetime(Some(&lifetime));
+            let (error, body) = convert_error(
+                quote! { match value { #arms } },
+                &failure.without_value().error_ty(input_ident, &ty_tys),
+                errors.get(variants[0].0),
+            );
             quote! {
                 #[automatically_derived]
-                impl #tys ::core::convert::TryInto<#ty> for #input_ident #tys {
-                    type Error = ::typesum::TryIntoError<#input_ident #tys>;
-                    fn try_into(self) -> ::core::result::Result<#ty, Self::Error> {
-                        match self {
-                            #(Self:: #idents (v) => Ok(v),)*
-                            #failed
-                        }
+                impl #ref_impl_tys ::core::convert::TryFrom<#borrow #input_ident #ty_tys>
+                    for #ty #where_clause
+                {
+                    type Error = #error;
+                    fn try_from(
+                        value: #borrow #input_ident #ty_tys,
+                    ) -> ::core::result::Result<Self, Self::Error> {
+                        #body
                     }
                 }
             }
         });
+    let try_from_impls = try_froms.iter().map(|(ty, variants)| {
+        let arms = generate_bucket_arms(variants, Access::Owned, input_ident, failure);
+        let (error, body) = convert_error(
+            quote! { match value { #arms } },
+            &owned_error,
+            errors.get(variants[0].0),
+        );
+        quote! {
+            #[automatically_derived]
+            impl #impl_tys ::core::convert::TryFrom<#input_ident #ty_tys> for #ty #where_clause {
+                type Error = #error;
+                fn try_from(
+                    value: #input_ident #ty_tys,
+                ) -> ::core::result::Result<Self, Self::Error> {
+                    #body
+                }
+            }
+        }
+    });
 
     quote! {
         #input_stripped
 
+        #(#views)*
+
         #(#try_into_impls)*
+        #(#try_from_impls)*
+        #(#try_from_ref_impls)*
+        #sum_type_impl
+        #(#delegate_impls)*
+        #has_impls
+        #ref_enums_defs
+        #visitor_defs
+        #fold_defs
+        #markers
 
         #[automatically_derived]
-        impl #tys #input_ident #tys {
+        impl #impl_tys #input_ident #ty_tys #where_clause {
             #try_into_names
             #mut_as_names
             #as_names
@@ -444,6 +2799,20 @@ pub fn sumtype_attr(mut attrs: Attrs, input: syn::DeriveInput) -> TokenStream {
             #try_as_impls
             #try_as_mut_impls
             #(#is_names)*
+            #map_impls
+            #map_into_impls
+            #and_then_impls
+            #inspect_impls
+            #unwrap_impls
+            #unwrap_ref_impls
+            #expect_impls
+            #or_impls
+            #or_else_impls
+            #as_dyn_impls
+            #ref_enums_fns
+            #visitor_fns
+            #match_with_fns
+            #has_fns
         }
         #(#from_impls)*
     }